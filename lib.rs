@@ -2,18 +2,41 @@ use anchor_lang::{
     prelude::*,
     system_program::{transfer, Transfer},
 };
-use pyth_solana_receiver_sdk::price_update::{get_feed_id_from_hex, PriceUpdateV2};
+use pyth_solana_receiver_sdk::price_update::{
+    get_feed_id_from_hex, PriceUpdateV2, VerificationLevel,
+};
+use switchboard_on_demand::{PullFeedAccountData, ON_DEMAND_DEVNET_PID, ON_DEMAND_MAINNET_PID};
 
 declare_id!("optnUT2hjddEs5BTojYaVE7V2A6st6sSnTVnsJpdY5F");
 
-#[cfg(feature = "devnet")]
-pub const PRICE_MAXIMUM_AGE: u64 = 7200; // 2 hours
-#[cfg(not(feature = "devnet"))]
-pub const PRICE_MAXIMUM_AGE: u64 = 5; // 5 seconds
-
 pub const MULTIPLIER_PRECISION: u128 = 1_000_000_000;
 
+pub const BASIS_POINTS_DIVISOR: u128 = 10_000;
+
+pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+
+/// Fixed-point scale of the cash-out pricing math, 9 decimal places
+pub const PROBABILITY_PRECISION: i128 = 1_000_000_000;
+
+/// ln 2 at PROBABILITY_PRECISION
+pub const LN_2: i128 = 693_147_181;
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+pub const MAX_BLACKOUT_WINDOWS: usize = 4;
+
+pub const ALL_TRADING_DAYS: u8 = 0b0111_1111;
+
+pub const MAX_REVENUE_RECIPIENTS: usize = 4;
+
+/// Switchboard results carry 18 decimals, rescaled to fit an i64 price
+pub const SWITCHBOARD_DECIMALS: u32 = 10;
+
 pub const HOUSE_AUTHORITY_PREFIX: &'static [u8] = b"house_authority";
+pub const SETTLEMENT_PRICE_PREFIX: &'static [u8] = b"settlement_price";
+pub const WITHDRAWAL_REQUEST_PREFIX: &'static [u8] = b"withdrawal_request";
+pub const LIQUIDITY_POSITION_PREFIX: &'static [u8] = b"liquidity_position";
+pub const EPOCH_PREFIX: &'static [u8] = b"epoch";
 
 #[program]
 pub mod optn {
@@ -22,9 +45,23 @@ pub mod optn {
     pub fn create_bet(
         ctx: Context<CreateBet>,
         direction: Direction,
+        kind: BetKind,
         lamports: u64,
         expiration: i64,
+        strike_offset_basis_points: i16,
     ) -> Result<()> {
+        // validate market mode
+        require!(
+            ctx.accounts.market.mode == MarketMode::FixedOdds,
+            OptnError::MarketModeMismatch
+        );
+
+        // validate market status
+        require!(
+            ctx.accounts.market.status == MarketStatus::Active,
+            OptnError::MarketInactive
+        );
+
         // validate wager amount
         require_gte!(lamports, ctx.accounts.house.min_wager, OptnError::MinWager);
         require_gte!(ctx.accounts.house.max_wager, lamports, OptnError::MaxWager);
@@ -35,17 +72,69 @@ pub mod optn {
             ctx.accounts.house.min_expiration,
             OptnError::MinExpiration
         );
+
+        // validate strike offset
         require_gte!(
-            ctx.accounts.house.max_expiration,
-            expiration,
-            OptnError::MaxExpiration
+            ctx.accounts.house.max_strike_offset_basis_points,
+            strike_offset_basis_points.unsigned_abs(),
+            OptnError::MaxStrikeOffset
         );
 
+        let multiplier = match kind {
+            BetKind::Binary => {
+                // scale the multiplier by how far out of the money the strike is
+                let out_of_the_money_basis_points = match direction {
+                    Direction::Buy => strike_offset_basis_points as i128,
+                    Direction::Sell => -(strike_offset_basis_points as i128),
+                };
+                let multiplier = ctx.accounts.house.multiplier as i128
+                    + out_of_the_money_basis_points
+                        * ctx.accounts.house.strike_multiplier_step as i128;
+                require_gt!(multiplier, 0, OptnError::MaxStrikeOffset);
+                multiplier
+            }
+            BetKind::Inside | BetKind::Outside | BetKind::Touch | BetKind::NoTouch => {
+                // range and touch bets are always placed relative to the entry price
+                require_eq!(strike_offset_basis_points, 0, OptnError::MaxStrikeOffset);
+                let multiplier = match kind {
+                    BetKind::Inside => ctx.accounts.house.range_in_multiplier,
+                    BetKind::Outside => ctx.accounts.house.range_out_multiplier,
+                    BetKind::Touch => ctx.accounts.house.touch_multiplier,
+                    _ => ctx.accounts.house.no_touch_multiplier,
+                };
+                require_gt!(multiplier, 0, OptnError::BetKindDisabled);
+                multiplier as i128
+            }
+        };
+
+        let clock = Clock::get()?;
+
+        // with delayed entry the entry price is fixed later by activate_bet
+        let delayed_entry = ctx.accounts.market.entry_window > 0;
+        let entry_price = if delayed_entry {
+            None
+        } else {
+            let price = ctx
+                .accounts
+                .market
+                .load_price(&ctx.accounts.price_update)?
+                .no_older_than(&clock, ctx.accounts.market.max_price_age)?;
+            ctx.accounts
+                .market
+                .cross_check(&price, ctx.accounts.secondary_price_update.as_deref())?;
+            Some(price)
+        };
+
+        let mut profit_amount =
+            u64::try_from(lamports as u128 * multiplier as u128 / MULTIPLIER_PRECISION).unwrap();
+        if let Some(price) = &entry_price {
+            profit_amount = ctx
+                .accounts
+                .market
+                .volatility_adjusted(profit_amount, price);
+        }
+
         // validate available liquidity
-        let profit_amount = u64::try_from(
-            lamports as u128 * ctx.accounts.house.multiplier as u128 / MULTIPLIER_PRECISION,
-        )
-        .unwrap();
         let reserved_liquidity = ctx.accounts.house.reserved_liquidity + profit_amount;
         require_gte!(
             ctx.accounts.house.liquidity,
@@ -56,12 +145,47 @@ pub mod optn {
         ctx.accounts.market.reserved_liquidity += profit_amount;
         ctx.accounts.market.total_wagered += lamports;
         ctx.accounts.market.active_bets += 1;
+        ctx.accounts.market.open_bets += 1;
 
         ctx.accounts.house.reserved_liquidity = reserved_liquidity;
         ctx.accounts.house.total_wagered += lamports;
         ctx.accounts.house.active_bets += 1;
 
-        let clock = Clock::get()?;
+        // snap the expiry up to the next market boundary when aligned
+        let mut expires_at = clock.unix_timestamp + expiration;
+        if ctx.accounts.market.expiry_interval > 0 {
+            expires_at += (ctx.accounts.market.expiry_interval
+                - expires_at % ctx.accounts.market.expiry_interval)
+                % ctx.accounts.market.expiry_interval;
+        }
+
+        // validate the snapped expiry still falls within the house maximum
+        require_gte!(
+            ctx.accounts.house.max_expiration,
+            expires_at - clock.unix_timestamp,
+            OptnError::MaxExpiration
+        );
+
+        // validate the feed is live when the bet settles
+        require!(
+            ctx.accounts.market.is_open(expires_at),
+            OptnError::MarketClosed
+        );
+
+        // touch barriers sit above the entry price for buys and below for sells
+        let strike_offset_basis_points = match (kind, direction) {
+            (BetKind::Touch | BetKind::NoTouch, Direction::Buy) => {
+                ctx.accounts.house.touch_barrier_basis_points as i16
+            }
+            (BetKind::Touch | BetKind::NoTouch, Direction::Sell) => {
+                -(ctx.accounts.house.touch_barrier_basis_points as i16)
+            }
+            _ => strike_offset_basis_points,
+        };
+        let range_basis_points = match kind {
+            BetKind::Inside | BetKind::Outside => ctx.accounts.house.range_basis_points,
+            _ => 0,
+        };
 
         ctx.accounts.bet.set_inner(Bet {
             market: ctx.accounts.market.key(),
@@ -69,25 +193,33 @@ pub mod optn {
             wagered_amount: lamports,
             profit_amount,
             final_payout: 0,
-            entry_price: ctx
-                .accounts
-                .price_update
-                .get_price_no_older_than(
-                    &clock,
-                    PRICE_MAXIMUM_AGE,
-                    &get_feed_id_from_hex(&ctx.accounts.market.feed_id[..])?,
-                )?
-                .price
-                .try_into()
-                .unwrap(),
+            entry_price: 0,
+            strike_price: 0,
+            lower_price: 0,
+            upper_price: 0,
+            high_price: 0,
+            low_price: 0,
             settled_price: 0,
+            strike_offset_basis_points,
+            range_basis_points,
             direction,
-            status: Status::Pending,
+            kind,
+            status: if delayed_entry {
+                Status::AwaitingEntry
+            } else {
+                Status::Pending
+            },
             created_at: clock.unix_timestamp,
-            expires_at: clock.unix_timestamp + expiration,
+            expires_at,
             settled_at: None,
         });
 
+        if let Some(price) = entry_price {
+            ctx.accounts
+                .bet
+                .set_entry_price(price.price.try_into().unwrap());
+        }
+
         emit_bet_created(&ctx.accounts.bet.key(), &ctx.accounts.bet);
         emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
         emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
@@ -104,39 +236,247 @@ pub mod optn {
         )
     }
 
-    /// Only admin can provide settled_price at the expired time for the bet
-    /// https://hermes.pyth.network/v2/updates/price/[expires_at]?ids%5B%5D=[feed_id]
-    pub fn settle_bet(ctx: Context<SettleBet>, settled_price: u64) -> Result<()> {
+    /// anyone can fix the entry price of a delayed entry bet from the first verified price
+    /// update published after its creation, within the market entry window
+    pub fn activate_bet(ctx: Context<ActivateBet>) -> Result<()> {
         // validate status
         require!(
-            ctx.accounts.bet.status == Status::Pending,
+            ctx.accounts.bet.status == Status::AwaitingEntry,
+            OptnError::BetActivated
+        );
+
+        // validate observation
+        let price = ctx.accounts.market.load_price(&ctx.accounts.price_update)?;
+        require!(
+            price.is_first_at_or_after(ctx.accounts.bet.created_at + 1),
+            OptnError::NotFirstPrice
+        );
+        ctx.accounts.market.cross_check_at(
+            &price,
+            ctx.accounts.secondary_price_update.as_deref(),
+            ctx.accounts.bet.created_at + 1,
+        )?;
+        require_gte!(
+            ctx.accounts.bet.created_at + ctx.accounts.market.entry_window,
+            price.publish_time,
+            OptnError::PriceOutsideEntryWindow
+        );
+
+        ctx.accounts
+            .bet
+            .set_entry_price(price.price.try_into().unwrap());
+        ctx.accounts.bet.status = Status::Pending;
+
+        // release the reservation the volatility adjustment no longer needs
+        let profit_amount = ctx
+            .accounts
+            .market
+            .volatility_adjusted(ctx.accounts.bet.profit_amount, &price);
+        let released_liquidity = ctx.accounts.bet.profit_amount - profit_amount;
+
+        ctx.accounts.bet.profit_amount = profit_amount;
+        ctx.accounts.market.reserved_liquidity -= released_liquidity;
+        ctx.accounts.house.reserved_liquidity -= released_liquidity;
+
+        emit_bet_updated(&ctx.accounts.bet.key(), &ctx.accounts.bet);
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        Ok(())
+    }
+
+    /// bettor can cancel a pending bet within the house cancel window, minus the cancel fee
+    pub fn cancel_bet(ctx: Context<CancelBet>) -> Result<()> {
+        // validate status
+        require!(
+            ctx.accounts.bet.status == Status::Pending
+                || ctx.accounts.bet.status == Status::AwaitingEntry,
             OptnError::BetSettled
         );
 
-        // validate expiry
+        // validate cancel window
+        require_gt!(
+            ctx.accounts.house.cancel_window,
+            0,
+            OptnError::CancelDisabled
+        );
         let current_time = Clock::get()?.unix_timestamp;
         require_gte!(
+            ctx.accounts.bet.created_at + ctx.accounts.house.cancel_window,
+            current_time,
+            OptnError::CancelWindowClosed
+        );
+
+        let cancel_fee = u64::try_from(
+            ctx.accounts.bet.wagered_amount as u128
+                * ctx.accounts.house.cancel_fee_basis_points as u128
+                / BASIS_POINTS_DIVISOR,
+        )
+        .unwrap();
+        let refund_amount = ctx.accounts.bet.wagered_amount - cancel_fee;
+
+        cancel(
+            &mut ctx.accounts.bet,
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.house,
+            refund_amount,
             current_time,
-            ctx.accounts.bet.expires_at,
-            OptnError::BetNotExpired
         );
+        ctx.accounts.house.total_profit += cancel_fee;
+
+        emit_bet_updated(&ctx.accounts.bet.key(), &ctx.accounts.bet);
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        validate_rent_floor(&ctx.accounts.house_authority, refund_amount)?;
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.house_authority.to_account_info(),
+                    to: ctx.accounts.authority.to_account_info(),
+                },
+            )
+            .with_signer(&[&[
+                HOUSE_AUTHORITY_PREFIX,
+                &ctx.accounts.house.key().to_bytes(),
+                &[ctx.accounts.house.authority_bump],
+            ]]),
+            refund_amount,
+        )
+    }
+
+    /// anyone can refund a pending bet that was not settled within the house settlement deadline,
+    /// or a delayed entry bet that was not activated within the market entry window minus the
+    /// cancel fee
+    pub fn refund_bet(ctx: Context<RefundBet>) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
 
-        let won = match ctx.accounts.bet.direction {
-            Direction::Buy => settled_price > ctx.accounts.bet.entry_price,
-            Direction::Sell => ctx.accounts.bet.entry_price > settled_price,
+        let refund_fee = match ctx.accounts.bet.status {
+            Status::Pending => {
+                // validate settlement deadline
+                require_gt!(
+                    ctx.accounts.house.settlement_deadline,
+                    0,
+                    OptnError::RefundDisabled
+                );
+                require_gte!(
+                    current_time,
+                    ctx.accounts.bet.expires_at + ctx.accounts.house.settlement_deadline,
+                    OptnError::SettlementDeadlineNotReached
+                );
+
+                0
+            }
+            Status::AwaitingEntry => {
+                // validate entry window
+                require_gt!(
+                    current_time,
+                    ctx.accounts.bet.created_at + ctx.accounts.market.entry_window,
+                    OptnError::EntryWindowOpen
+                );
+
+                // skipping activation costs the cancel fee, or it would be a free option
+                u64::try_from(
+                    ctx.accounts.bet.wagered_amount as u128
+                        * ctx.accounts.house.cancel_fee_basis_points as u128
+                        / BASIS_POINTS_DIVISOR,
+                )
+                .unwrap()
+            }
+            _ => return err!(OptnError::BetSettled),
         };
 
-        if won {
-            ctx.accounts.bet.status = Status::Won;
-            ctx.accounts.bet.final_payout =
-                ctx.accounts.bet.wagered_amount + ctx.accounts.bet.profit_amount;
+        let refund_amount = ctx.accounts.bet.wagered_amount - refund_fee;
 
-            ctx.accounts.house.liquidity -= ctx.accounts.bet.profit_amount; // house lose
-        } else {
-            ctx.accounts.bet.status = Status::Lose;
+        cancel(
+            &mut ctx.accounts.bet,
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.house,
+            refund_amount,
+            current_time,
+        );
+        ctx.accounts.house.total_profit += refund_fee;
+
+        emit_bet_updated(&ctx.accounts.bet.key(), &ctx.accounts.bet);
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        validate_rent_floor(&ctx.accounts.house_authority, refund_amount)?;
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.house_authority.to_account_info(),
+                    to: ctx.accounts.authority.to_account_info(),
+                },
+            )
+            .with_signer(&[&[
+                HOUSE_AUTHORITY_PREFIX,
+                &ctx.accounts.house.key().to_bytes(),
+                &[ctx.accounts.house.authority_bump],
+            ]]),
+            refund_amount,
+        )
+    }
+
+    /// bettor can sell a pending bet back to the house at its fair value minus the spread
+    pub fn cash_out(ctx: Context<CashOut>) -> Result<()> {
+        // validate status
+        require!(
+            ctx.accounts.bet.status == Status::Pending,
+            OptnError::BetSettled
+        );
+        require_gt!(
+            ctx.accounts.market.volatility_basis_points,
+            0,
+            OptnError::CashOutDisabled
+        );
+
+        // validate expiry
+        let clock = Clock::get()?;
+        require_gt!(
+            ctx.accounts.bet.expires_at,
+            clock.unix_timestamp,
+            OptnError::BetExpired
+        );
+
+        let price = ctx
+            .accounts
+            .market
+            .load_price(&ctx.accounts.price_update)?
+            .no_older_than(&clock, ctx.accounts.market.max_price_age)?;
+        ctx.accounts
+            .market
+            .cross_check(&price, ctx.accounts.secondary_price_update.as_deref())?;
+
+        // validate price
+        require_gt!(price.price, 0, OptnError::NonPositivePrice);
+        let current_price: u64 = price.price.try_into().unwrap();
 
-            // TODO: cut % of house profit for revenue
-            ctx.accounts.house.liquidity += ctx.accounts.bet.wagered_amount; // house profit
+        ctx.accounts.bet.record_price(current_price);
+
+        let cash_out_amount = cash_out_value(
+            &ctx.accounts.bet,
+            current_price,
+            ctx.accounts.market.volatility_basis_points,
+            ctx.accounts.house.cash_out_spread_basis_points,
+            ctx.accounts.bet.expires_at - clock.unix_timestamp,
+        )?;
+
+        ctx.accounts.bet.status = Status::CashedOut;
+        ctx.accounts.bet.final_payout = cash_out_amount;
+        ctx.accounts.bet.settled_price = current_price;
+        ctx.accounts.bet.settled_at = Some(clock.unix_timestamp);
+
+        if cash_out_amount > ctx.accounts.bet.wagered_amount {
+            ctx.accounts.house.liquidity -= cash_out_amount - ctx.accounts.bet.wagered_amount;
+        // house lose
+        } else {
+            ctx.accounts.house.liquidity += ctx.accounts.bet.wagered_amount - cash_out_amount;
+            // house profit
         }
 
         ctx.accounts.house.active_bets -= 1;
@@ -145,12 +485,85 @@ pub mod optn {
         ctx.accounts.house.reserved_liquidity -= ctx.accounts.bet.profit_amount;
 
         ctx.accounts.market.active_bets -= 1;
+        ctx.accounts.market.open_bets -= 1;
         ctx.accounts.market.settled_bets += 1;
         ctx.accounts.market.total_wagered -= ctx.accounts.bet.wagered_amount;
         ctx.accounts.market.reserved_liquidity -= ctx.accounts.bet.profit_amount;
 
-        ctx.accounts.bet.settled_price = settled_price;
-        ctx.accounts.bet.settled_at = Some(current_time);
+        emit_bet_updated(&ctx.accounts.bet.key(), &ctx.accounts.bet);
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        if cash_out_amount > 0 {
+            validate_rent_floor(&ctx.accounts.house_authority, cash_out_amount)?;
+
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.house_authority.to_account_info(),
+                        to: ctx.accounts.authority.to_account_info(),
+                    },
+                )
+                .with_signer(&[&[
+                    HOUSE_AUTHORITY_PREFIX,
+                    &ctx.accounts.house.key().to_bytes(),
+                    &[ctx.accounts.house.authority_bump],
+                ]]),
+                cash_out_amount,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// anyone can record a verified price observation on a pending touch bet
+    pub fn record_price(ctx: Context<RecordPrice>) -> Result<()> {
+        // validate status
+        require!(
+            ctx.accounts.bet.status == Status::Pending,
+            OptnError::BetSettled
+        );
+        require!(
+            matches!(ctx.accounts.bet.kind, BetKind::Touch | BetKind::NoTouch),
+            OptnError::NotTouchBet
+        );
+
+        // validate observation
+        let price = ctx.accounts.market.load_price(&ctx.accounts.price_update)?;
+        ctx.accounts
+            .market
+            .cross_check(&price, ctx.accounts.secondary_price_update.as_deref())?;
+        require!(
+            price.publish_time >= ctx.accounts.bet.created_at
+                && ctx.accounts.bet.expires_at >= price.publish_time,
+            OptnError::PriceOutsideBetWindow
+        );
+
+        ctx.accounts
+            .bet
+            .record_price(price.price.try_into().unwrap());
+
+        emit_bet_updated(&ctx.accounts.bet.key(), &ctx.accounts.bet);
+
+        Ok(())
+    }
+
+    /// anyone can settle an expired bet at the recorded settlement price for its expiry,
+    /// provisional admin prices hold settlement (and so payouts) until the dispute window passes
+    pub fn settle_bet(ctx: Context<SettleBet>) -> Result<()> {
+        require_gte!(
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.settlement_price.disputable_until,
+            OptnError::SettlementPriceProvisional
+        );
+
+        settle(
+            &mut ctx.accounts.bet,
+            &mut ctx.accounts.market,
+            &mut ctx.accounts.house,
+            ctx.accounts.settlement_price.price,
+        )?;
 
         emit_bet_updated(&ctx.accounts.bet.key(), &ctx.accounts.bet);
         emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
@@ -159,15 +572,149 @@ pub mod optn {
         Ok(())
     }
 
+    /// anyone can record the settlement price at a timestamp from the first verified price
+    /// update published at or after the timestamp
+    /// https://hermes.pyth.network/v2/updates/price/[expires_at]?ids%5B%5D=[feed_id]
+    pub fn record_settlement_price(
+        ctx: Context<RecordSettlementPrice>,
+        timestamp: i64,
+    ) -> Result<()> {
+        // validate the oracles can prove ordering, otherwise only the admin sets the price
+        require!(
+            ctx.accounts.market.proves_price_order(),
+            OptnError::UnorderedPriceSource
+        );
+
+        // validate observation
+        let price = ctx.accounts.market.load_price(&ctx.accounts.price_update)?;
+        require!(
+            price.is_first_at_or_after(timestamp),
+            OptnError::NotFirstPrice
+        );
+        ctx.accounts.market.cross_check_at(
+            &price,
+            ctx.accounts.secondary_price_update.as_deref(),
+            timestamp,
+        )?;
+
+        ctx.accounts.settlement_price.set_inner(SettlementPrice {
+            market: ctx.accounts.market.key(),
+            timestamp,
+            price: price.price.try_into().unwrap(),
+            publish_time: price.publish_time,
+            source: SettlementSource::Oracle,
+            disputable_until: 0,
+            bump: ctx.bumps.settlement_price,
+        });
+
+        emit_settlement_price_updated(
+            &ctx.accounts.settlement_price.key(),
+            &ctx.accounts.settlement_price,
+        );
+
+        Ok(())
+    }
+
+    /// Only admin can provide the settlement price when no verified price update is available
+    pub fn set_settlement_price(
+        ctx: Context<SetSettlementPrice>,
+        timestamp: i64,
+        price: u64,
+    ) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+        require_gte!(current_time, timestamp);
+
+        // admin prices stay provisional during the dispute window, they are final on markets
+        // whose oracles cannot prove ordering since no dispute could pass
+        let disputable_until =
+            if ctx.accounts.house.dispute_window > 0 && ctx.accounts.market.proves_price_order() {
+                current_time + ctx.accounts.house.dispute_window
+            } else {
+                0
+            };
+
+        ctx.accounts.settlement_price.set_inner(SettlementPrice {
+            market: ctx.accounts.market.key(),
+            timestamp,
+            price,
+            publish_time: 0,
+            source: SettlementSource::Admin,
+            disputable_until,
+            bump: ctx.bumps.settlement_price,
+        });
+
+        emit_settlement_price_updated(
+            &ctx.accounts.settlement_price.key(),
+            &ctx.accounts.settlement_price,
+        );
+
+        Ok(())
+    }
+
+    /// anyone can replace a provisional admin price with the first verified price published
+    /// at or after its timestamp when they differ, the replacement is final
+    pub fn dispute_settlement_price(ctx: Context<DisputeSettlementPrice>) -> Result<()> {
+        // validate dispute window
+        require!(
+            ctx.accounts.settlement_price.source == SettlementSource::Admin,
+            OptnError::DisputeWindowClosed
+        );
+        require_gt!(
+            ctx.accounts.settlement_price.disputable_until,
+            Clock::get()?.unix_timestamp,
+            OptnError::DisputeWindowClosed
+        );
+
+        // validate observation
+        let price = ctx.accounts.market.load_price(&ctx.accounts.price_update)?;
+        require!(
+            price.is_first_at_or_after(ctx.accounts.settlement_price.timestamp),
+            OptnError::NotFirstPrice
+        );
+        ctx.accounts.market.cross_check_at(
+            &price,
+            ctx.accounts.secondary_price_update.as_deref(),
+            ctx.accounts.settlement_price.timestamp,
+        )?;
+
+        let disputed_price: u64 = price.price.try_into().unwrap();
+        require_neq!(
+            disputed_price,
+            ctx.accounts.settlement_price.price,
+            OptnError::SettlementPriceMatches
+        );
+
+        ctx.accounts.settlement_price.price = disputed_price;
+        ctx.accounts.settlement_price.publish_time = price.publish_time;
+        ctx.accounts.settlement_price.source = SettlementSource::Oracle;
+        ctx.accounts.settlement_price.disputable_until = 0;
+
+        emit_settlement_price_updated(
+            &ctx.accounts.settlement_price.key(),
+            &ctx.accounts.settlement_price,
+        );
+
+        Ok(())
+    }
+
     /// close bet account and reclaim rent fee back to the user
     pub fn close_bet(ctx: Context<CloseBet>) -> Result<()> {
-        // validate status
+        // validate status, canceled and cashed out bets close when they pay out
         require!(
-            ctx.accounts.bet.status != Status::Pending,
+            ctx.accounts.bet.status == Status::Won || ctx.accounts.bet.status == Status::Lose,
             OptnError::BetPending
         );
 
+        ctx.accounts.market.open_bets -= 1;
+        ctx.accounts.market.unclaimed_payouts -= ctx.accounts.bet.final_payout;
+        ctx.accounts.house.unclaimed_payouts -= ctx.accounts.bet.final_payout;
+
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
         if ctx.accounts.bet.final_payout > 0 {
+            validate_rent_floor(&ctx.accounts.house_authority, ctx.accounts.bet.final_payout)?;
+
             transfer(
                 CpiContext::new(
                     ctx.accounts.system_program.to_account_info(),
@@ -188,11 +735,43 @@ pub mod optn {
         Ok(())
     }
 
-    /// anyone can deposit liquidity
+    pub fn create_liquidity_position(ctx: Context<CreateLiquidityPosition>) -> Result<()> {
+        ctx.accounts
+            .liquidity_position
+            .set_inner(LiquidityPosition {
+                house: ctx.accounts.house.key(),
+                authority: ctx.accounts.authority.key(),
+                shares: 0,
+                locked_until: 0,
+                withdrawal_shares: 0,
+                withdrawal_epoch: 0,
+                bump: ctx.bumps.liquidity_position,
+            });
+
+        Ok(())
+    }
+
+    /// anyone can deposit liquidity for shares priced at the maximum liquidity per share
     pub fn deposit_liquidity(ctx: Context<DepositLiquidity>, lamports: u64) -> Result<()> {
+        ctx.accounts.house.share_unshared_liquidity();
+
+        let shares = ctx.accounts.house.shares_for(lamports)?;
+        require_gt!(shares, 0);
+
+        ctx.accounts.liquidity_position.shares += shares;
+        ctx.accounts.liquidity_position.locked_until =
+            Clock::get()?.unix_timestamp + ctx.accounts.house.lock_period;
+
+        ctx.accounts.house.total_shares += shares;
         ctx.accounts.house.liquidity += lamports;
         ctx.accounts.house.total_deposits += lamports;
 
+        emit_liquidity_position_updated(
+            &ctx.accounts.liquidity_position.key(),
+            &ctx.accounts.liquidity_position,
+        );
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
         transfer(
             CpiContext::new(
                 ctx.accounts.system_program.to_account_info(),
@@ -205,18 +784,240 @@ pub mod optn {
         )
     }
 
-    /// beneficiary can withdraw liquidity
-    pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, lamports: u64) -> Result<()> {
-        let available_liquidity =
-            ctx.accounts.house.liquidity - ctx.accounts.house.reserved_liquidity;
+    /// queued shares are redeemed when the current epoch ends, at its share price
+    pub fn queue_liquidity_withdrawal(
+        ctx: Context<QueueLiquidityWithdrawal>,
+        shares: u64,
+    ) -> Result<()> {
+        require_gt!(shares, 0);
+
+        // validate lockup
         require_gte!(
-            available_liquidity,
-            lamports,
-            OptnError::InsufficientLiquidity
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.liquidity_position.locked_until,
+            OptnError::LiquidityLocked
         );
 
-        ctx.accounts.house.liquidity -= lamports;
-        ctx.accounts.house.total_withdrawals += lamports;
+        // validate nothing is already queued
+        require_eq!(
+            ctx.accounts.liquidity_position.withdrawal_shares,
+            0,
+            OptnError::LiquidityWithdrawalQueued
+        );
+
+        require_gte!(
+            ctx.accounts.liquidity_position.shares,
+            shares,
+            OptnError::InsufficientShares
+        );
+
+        ctx.accounts.liquidity_position.shares -= shares;
+        ctx.accounts.liquidity_position.withdrawal_shares = shares;
+        ctx.accounts.liquidity_position.withdrawal_epoch = ctx.accounts.house.epoch;
+
+        ctx.accounts.house.pending_withdrawal_shares += shares;
+
+        emit_liquidity_position_updated(
+            &ctx.accounts.liquidity_position.key(),
+            &ctx.accounts.liquidity_position,
+        );
+
+        Ok(())
+    }
+
+    /// anyone can end an epoch once it has run its duration, snapshotting the share price
+    /// and redeeming the queued shares
+    pub fn advance_epoch(ctx: Context<AdvanceEpoch>) -> Result<()> {
+        let current_time = Clock::get()?.unix_timestamp;
+
+        // validate epoch duration
+        require_gte!(
+            current_time,
+            ctx.accounts.house.epoch_started_at + ctx.accounts.house.epoch_duration,
+            OptnError::EpochNotEnded
+        );
+
+        let house = &mut ctx.accounts.house;
+
+        if house.total_shares > 0 {
+            house.share_price = u64::try_from(
+                house.min_liquidity() as u128 * MULTIPLIER_PRECISION / house.total_shares as u128,
+            )
+            .unwrap();
+        } else {
+            house.share_price = MULTIPLIER_PRECISION as u64;
+        }
+
+        let withdrawal_amount = u64::try_from(
+            house.pending_withdrawal_shares as u128 * house.share_price as u128
+                / MULTIPLIER_PRECISION,
+        )
+        .unwrap();
+
+        // the share price is net of reserved liquidity, so the withdrawal is always free liquidity
+        house.liquidity -= withdrawal_amount;
+        house.total_withdrawals += withdrawal_amount;
+        house.unclaimed_payouts += withdrawal_amount;
+        house.unclaimed_withdrawals += withdrawal_amount;
+        house.total_shares -= house.pending_withdrawal_shares;
+
+        ctx.accounts.epoch.set_inner(Epoch {
+            house: house.key(),
+            epoch: house.epoch,
+            share_price: house.share_price,
+            withdrawal_shares: house.pending_withdrawal_shares,
+            withdrawal_amount,
+            started_at: house.epoch_started_at,
+            ended_at: current_time,
+            final_epoch: false,
+            bump: ctx.bumps.epoch,
+        });
+
+        house.pending_withdrawal_shares = 0;
+        house.epoch += 1;
+        house.epoch_started_at = current_time;
+
+        emit_epoch_updated(&ctx.accounts.epoch.key(), &ctx.accounts.epoch);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        Ok(())
+    }
+
+    /// pay out shares redeemed at the end of the epoch they were queued in
+    pub fn claim_liquidity_withdrawal(ctx: Context<ClaimLiquidityWithdrawal>) -> Result<()> {
+        let shares = ctx.accounts.liquidity_position.withdrawal_shares;
+        require_gt!(shares, 0, OptnError::NoLiquidityWithdrawal);
+
+        let payout = ctx.accounts.epoch.payout(shares);
+
+        ctx.accounts.epoch.withdrawal_shares -= shares;
+        ctx.accounts.epoch.withdrawal_amount -= payout;
+        ctx.accounts.house.unclaimed_payouts -= payout;
+        ctx.accounts.house.unclaimed_withdrawals -= payout;
+
+        ctx.accounts.liquidity_position.withdrawal_shares = 0;
+
+        emit_liquidity_position_updated(
+            &ctx.accounts.liquidity_position.key(),
+            &ctx.accounts.liquidity_position,
+        );
+        emit_epoch_updated(&ctx.accounts.epoch.key(), &ctx.accounts.epoch);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        validate_rent_floor(&ctx.accounts.house_authority, payout)?;
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.house_authority.to_account_info(),
+                    to: ctx.accounts.authority.to_account_info(),
+                },
+            )
+            .with_signer(&[&[
+                HOUSE_AUTHORITY_PREFIX,
+                &ctx.accounts.house.key().to_bytes(),
+                &[ctx.accounts.house.authority_bump],
+            ]]),
+            payout,
+        )
+    }
+
+    /// pay out shares redeemed before the house closed, or left in the position when it closed,
+    /// from what the house authority kept for liquidity providers
+    pub fn claim_closed_liquidity_withdrawal(
+        ctx: Context<ClaimClosedLiquidityWithdrawal>,
+    ) -> Result<()> {
+        // validate the house was closed
+        require!(
+            ctx.accounts.house.data_is_empty(),
+            OptnError::HouseNotClosed
+        );
+
+        let position = &mut ctx.accounts.liquidity_position;
+        let mut shares = 0;
+        if position.withdrawal_epoch == ctx.accounts.epoch.epoch {
+            shares += position.withdrawal_shares;
+            position.withdrawal_shares = 0;
+        }
+        if ctx.accounts.epoch.final_epoch {
+            shares += position.shares;
+            position.shares = 0;
+        }
+        require_gt!(shares, 0, OptnError::NoLiquidityWithdrawal);
+
+        let payout = ctx.accounts.epoch.payout(shares);
+
+        ctx.accounts.epoch.withdrawal_shares -= shares;
+        ctx.accounts.epoch.withdrawal_amount -= payout;
+
+        emit_liquidity_position_updated(
+            &ctx.accounts.liquidity_position.key(),
+            &ctx.accounts.liquidity_position,
+        );
+        emit_epoch_updated(&ctx.accounts.epoch.key(), &ctx.accounts.epoch);
+
+        validate_rent_floor(&ctx.accounts.house_authority, payout)?;
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.house_authority.to_account_info(),
+                    to: ctx.accounts.authority.to_account_info(),
+                },
+            )
+            .with_signer(&[&[
+                HOUSE_AUTHORITY_PREFIX,
+                &ctx.accounts.house.key().to_bytes(),
+                &[ctx.bumps.house_authority],
+            ]]),
+            payout,
+        )
+    }
+
+    /// beneficiary can deposit liquidity for beneficiary shares, outside any lockup
+    pub fn deposit_house_liquidity(ctx: Context<WithdrawLiquidity>, lamports: u64) -> Result<()> {
+        ctx.accounts.house.share_unshared_liquidity();
+
+        let shares = ctx.accounts.house.shares_for(lamports)?;
+        require_gt!(shares, 0);
+
+        ctx.accounts.house.beneficiary_shares += shares;
+        ctx.accounts.house.total_shares += shares;
+        ctx.accounts.house.liquidity += lamports;
+        ctx.accounts.house.total_deposits += lamports;
+
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.beneficiary.to_account_info(),
+                    to: ctx.accounts.house_authority.to_account_info(),
+                },
+            ),
+            lamports,
+        )
+    }
+
+    /// beneficiary can withdraw liquidity backed by the beneficiary shares
+    pub fn withdraw_liquidity(ctx: Context<WithdrawLiquidity>, lamports: u64) -> Result<()> {
+        // validate instant withdrawals are allowed
+        require_eq!(
+            ctx.accounts
+                .house
+                .withdrawal_delay_at(Clock::get()?.unix_timestamp),
+            0,
+            OptnError::WithdrawalDelayed
+        );
+
+        withdraw(
+            &mut ctx.accounts.house,
+            &ctx.accounts.house_authority,
+            lamports,
+        )?;
 
         transfer(
             CpiContext::new(
@@ -235,12 +1036,54 @@ pub mod optn {
         )
     }
 
-    /// beneficiary can claim for profit
-    pub fn claim_profit(ctx: Context<WithdrawLiquidity>) -> Result<()> {
-        require_gt!(ctx.accounts.house.total_profit, 0, OptnError::NoProfit);
+    /// beneficiary queues a withdrawal executable once the house withdrawal delay passes
+    pub fn request_withdrawal(ctx: Context<RequestWithdrawal>, lamports: u64) -> Result<()> {
+        require_gt!(lamports, 0);
 
-        ctx.accounts.house.claimed_profits += ctx.accounts.house.total_profit;
-        ctx.accounts.house.total_profit = 0;
+        let requested_at = Clock::get()?.unix_timestamp;
+
+        ctx.accounts
+            .withdrawal_request
+            .set_inner(WithdrawalRequest {
+                house: ctx.accounts.house.key(),
+                amount: lamports,
+                status: WithdrawalStatus::Pending,
+                requested_at,
+                executable_at: requested_at + ctx.accounts.house.withdrawal_delay_at(requested_at),
+                bump: ctx.bumps.withdrawal_request,
+            });
+
+        emit_withdrawal_request_updated(
+            &ctx.accounts.withdrawal_request.key(),
+            &ctx.accounts.withdrawal_request,
+        );
+
+        Ok(())
+    }
+
+    /// liquidity is checked again when the withdrawal executes
+    pub fn execute_withdrawal(ctx: Context<ExecuteWithdrawal>) -> Result<()> {
+        // validate delay
+        require_gte!(
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.withdrawal_request.executable_at,
+            OptnError::WithdrawalDelayed
+        );
+
+        let lamports = ctx.accounts.withdrawal_request.amount;
+
+        withdraw(
+            &mut ctx.accounts.house,
+            &ctx.accounts.house_authority,
+            lamports,
+        )?;
+
+        ctx.accounts.withdrawal_request.status = WithdrawalStatus::Executed;
+
+        emit_withdrawal_request_updated(
+            &ctx.accounts.withdrawal_request.key(),
+            &ctx.accounts.withdrawal_request,
+        );
 
         transfer(
             CpiContext::new(
@@ -255,10 +1098,258 @@ pub mod optn {
                 &ctx.accounts.house.key().to_bytes(),
                 &[ctx.accounts.house.authority_bump],
             ]]),
-            ctx.accounts.house.total_profit,
+            lamports,
         )
     }
 
+    pub fn cancel_withdrawal(ctx: Context<CancelWithdrawal>) -> Result<()> {
+        ctx.accounts.withdrawal_request.status = WithdrawalStatus::Canceled;
+
+        emit_withdrawal_request_updated(
+            &ctx.accounts.withdrawal_request.key(),
+            &ctx.accounts.withdrawal_request,
+        );
+
+        Ok(())
+    }
+
+    /// beneficiary can claim for profit, split across the revenue split recipients passed as
+    /// remaining accounts in table order, with the rounding remainder going to the beneficiary
+    pub fn claim_profit<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawLiquidity<'info>>,
+    ) -> Result<()> {
+        let total_profit = ctx.accounts.house.total_profit;
+        require_gt!(total_profit, 0, OptnError::NoProfit);
+        validate_rent_floor(&ctx.accounts.house_authority, total_profit)?;
+
+        ctx.accounts.house.claimed_profits += total_profit;
+        ctx.accounts.house.total_profit = 0;
+
+        let revenue_split: Vec<RevenueShare> = ctx
+            .accounts
+            .house
+            .revenue_split
+            .iter()
+            .filter(|share| share.basis_points > 0)
+            .copied()
+            .collect();
+
+        // validate recipients
+        require_eq!(
+            ctx.remaining_accounts.len(),
+            revenue_split.len(),
+            OptnError::RevenueRecipientMismatch
+        );
+
+        let mut recipients = Vec::with_capacity(revenue_split.len() + 1);
+        let mut amounts = Vec::with_capacity(revenue_split.len() + 1);
+
+        let mut remaining_amount = total_profit;
+        for (share, recipient) in revenue_split.iter().zip(ctx.remaining_accounts) {
+            require_keys_eq!(
+                recipient.key(),
+                share.recipient,
+                OptnError::RevenueRecipientMismatch
+            );
+
+            let amount = u64::try_from(
+                total_profit as u128 * share.basis_points as u128 / BASIS_POINTS_DIVISOR,
+            )
+            .unwrap();
+            remaining_amount -= amount;
+
+            recipients.push(share.recipient);
+            amounts.push(amount);
+
+            if amount > 0 {
+                transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        Transfer {
+                            from: ctx.accounts.house_authority.to_account_info(),
+                            to: recipient.clone(),
+                        },
+                    )
+                    .with_signer(&[&[
+                        HOUSE_AUTHORITY_PREFIX,
+                        &ctx.accounts.house.key().to_bytes(),
+                        &[ctx.accounts.house.authority_bump],
+                    ]]),
+                    amount,
+                )?;
+            }
+        }
+
+        if remaining_amount > 0 {
+            recipients.push(ctx.accounts.beneficiary.key());
+            amounts.push(remaining_amount);
+
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.house_authority.to_account_info(),
+                        to: ctx.accounts.beneficiary.to_account_info(),
+                    },
+                )
+                .with_signer(&[&[
+                    HOUSE_AUTHORITY_PREFIX,
+                    &ctx.accounts.house.key().to_bytes(),
+                    &[ctx.accounts.house.authority_bump],
+                ]]),
+                remaining_amount,
+            )?;
+        }
+
+        emit!(ProfitDistributedEvent {
+            pubkey: ctx.accounts.house.key(),
+            data: ProfitDistributedData {
+                total_profit,
+                recipients,
+                amounts,
+            },
+        });
+
+        Ok(())
+    }
+
+    /// anyone can check the house authority balance covers everything the house owes
+    pub fn audit_house(ctx: Context<AuditHouse>) -> Result<()> {
+        let house = &ctx.accounts.house;
+
+        let balance = ctx.accounts.house_authority.lamports();
+        let rent_floor = Rent::get()?.minimum_balance(0);
+        let obligations = house.liquidity
+            + house.total_wagered
+            + house.total_profit
+            + house.unclaimed_payouts
+            + rent_floor;
+
+        emit!(SolvencyReportEvent {
+            pubkey: house.key(),
+            data: SolvencyReportData {
+                balance,
+                liquidity: house.liquidity,
+                total_wagered: house.total_wagered,
+                total_profit: house.total_profit,
+                unclaimed_payouts: house.unclaimed_payouts,
+                rent_floor,
+                obligations,
+            },
+        });
+
+        require_gte!(balance, obligations, OptnError::Insolvent);
+
+        Ok(())
+    }
+
+    /// sweep the house authority to the beneficiary and close the house, shares left with
+    /// liquidity providers are redeemed pro rata in a final epoch they claim after close
+    pub fn close_house(ctx: Context<CloseHouse>) -> Result<()> {
+        // validate nothing is left to settle
+        require_eq!(
+            ctx.accounts.house.active_bets,
+            0,
+            OptnError::HouseHasActiveBets
+        );
+        require_eq!(
+            ctx.accounts.house.active_markets,
+            0,
+            OptnError::HouseHasActiveMarkets
+        );
+        require_eq!(
+            ctx.accounts.house.unclaimed_payouts,
+            ctx.accounts.house.unclaimed_withdrawals,
+            OptnError::HouseHasUnclaimedPayouts
+        );
+
+        // validate profit went through the revenue split
+        require_eq!(
+            ctx.accounts.house.total_profit,
+            0,
+            OptnError::UnclaimedProfit
+        );
+
+        // redeem every share left with liquidity providers, nothing is reserved once bets and
+        // markets are closed
+        let house = &ctx.accounts.house;
+        let share_price = if house.total_shares > 0 {
+            u64::try_from(
+                house.liquidity as u128 * MULTIPLIER_PRECISION / house.total_shares as u128,
+            )
+            .unwrap()
+        } else {
+            MULTIPLIER_PRECISION as u64
+        };
+        let withdrawal_shares = house.total_shares - house.beneficiary_shares;
+        let withdrawal_amount =
+            u64::try_from(withdrawal_shares as u128 * share_price as u128 / MULTIPLIER_PRECISION)
+                .unwrap();
+
+        ctx.accounts.epoch.set_inner(Epoch {
+            house: house.key(),
+            epoch: house.epoch,
+            share_price,
+            withdrawal_shares,
+            withdrawal_amount,
+            started_at: house.epoch_started_at,
+            ended_at: Clock::get()?.unix_timestamp,
+            final_epoch: true,
+            bump: ctx.bumps.epoch,
+        });
+
+        emit_epoch_updated(&ctx.accounts.epoch.key(), &ctx.accounts.epoch);
+
+        // keep what liquidity providers are owed, above the rent floor while anything is owed
+        let retained = house.unclaimed_withdrawals + withdrawal_amount;
+        let retained = if retained > 0 {
+            retained + Rent::get()?.minimum_balance(0)
+        } else {
+            0
+        };
+        let amount = ctx
+            .accounts
+            .house_authority
+            .lamports()
+            .saturating_sub(retained);
+
+        emit!(HouseClosedEvent {
+            pubkey: ctx.accounts.house.key(),
+            data: HouseClosedData {
+                beneficiary: ctx.accounts.beneficiary.key(),
+                amount,
+                liquidity: ctx.accounts.house.liquidity,
+                total_profit: ctx.accounts.house.total_profit,
+                total_deposits: ctx.accounts.house.total_deposits,
+                total_withdrawals: ctx.accounts.house.total_withdrawals,
+                total_wagered: ctx.accounts.house.total_wagered,
+                claimed_profits: ctx.accounts.house.claimed_profits,
+                settled_bets: ctx.accounts.house.settled_bets,
+                canceled_bets: ctx.accounts.house.canceled_bets,
+            },
+        });
+
+        if amount > 0 {
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.house_authority.to_account_info(),
+                        to: ctx.accounts.beneficiary.to_account_info(),
+                    },
+                )
+                .with_signer(&[&[
+                    HOUSE_AUTHORITY_PREFIX,
+                    &ctx.accounts.house.key().to_bytes(),
+                    &[ctx.accounts.house.authority_bump],
+                ]]),
+                amount,
+            )?;
+        }
+
+        Ok(())
+    }
+
     pub fn create_house(
         ctx: Context<CreateHouse>,
         beneficiary: Pubkey,
@@ -268,6 +1359,7 @@ pub mod optn {
         max_expiration: i64,
         multiplier: u64,
         fee_basis_points: u16,
+        max_price_age: u64,
     ) -> Result<()> {
         require_gt!(min_wager, 0);
         require_gt!(max_wager, min_wager);
@@ -275,6 +1367,8 @@ pub mod optn {
         require_gt!(min_expiration, 0);
         require_gt!(max_expiration, min_expiration);
 
+        require_gt!(max_price_age, 0);
+
         ctx.accounts.house.set_inner(House {
             admin: ctx.accounts.admin.key(),
             beneficiary,
@@ -285,75 +1379,1166 @@ pub mod optn {
             total_wagered: 0,
             total_profit: 0,
             claimed_profits: 0,
+            unclaimed_payouts: 0,
+            unclaimed_withdrawals: 0,
             active_bets: 0,
             settled_bets: 0,
             canceled_bets: 0,
+            active_markets: 0,
             min_wager,
             max_wager,
             min_expiration,
             max_expiration,
             multiplier,
             fee_basis_points,
+            max_price_age,
+            cancel_window: 0,
+            cancel_fee_basis_points: 0,
+            cash_out_spread_basis_points: 0,
+            max_strike_offset_basis_points: 0,
+            strike_multiplier_step: 0,
+            range_basis_points: 0,
+            range_in_multiplier: 0,
+            range_out_multiplier: 0,
+            touch_barrier_basis_points: 0,
+            touch_multiplier: 0,
+            no_touch_multiplier: 0,
+            dispute_window: 0,
+            settlement_deadline: 0,
+            withdrawal_delay: 0,
+            pending_withdrawal_delay: 0,
+            withdrawal_delay_effective_at: 0,
+            total_shares: 0,
+            beneficiary_shares: 0,
+            pending_withdrawal_shares: 0,
+            share_price: MULTIPLIER_PRECISION as u64,
+            lock_period: 0,
+            epoch: 0,
+            epoch_started_at: Clock::get()?.unix_timestamp,
+            epoch_duration: SECONDS_PER_DAY,
+            revenue_split: [RevenueShare::default(); MAX_REVENUE_RECIPIENTS],
             authority_bump: ctx.bumps.house_authority,
         });
 
-        Ok(())
-    }
+        // fund the authority up to its rent-exempt minimum, kept out of liquidity
+        let rent_floor = Rent::get()?
+            .minimum_balance(0)
+            .saturating_sub(ctx.accounts.house_authority.lamports());
+        if rent_floor > 0 {
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.admin.to_account_info(),
+                        to: ctx.accounts.house_authority.to_account_info(),
+                    },
+                ),
+                rent_floor,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn update_wager_limits(
+        ctx: Context<UpdateHouse>,
+        min_wager: u64,
+        max_wager: u64,
+    ) -> Result<()> {
+        require_gt!(min_wager, 0);
+        require_gt!(max_wager, min_wager);
+
+        ctx.accounts.house.min_wager = min_wager;
+        ctx.accounts.house.max_wager = max_wager;
+
+        Ok(())
+    }
+
+    /// default price max age for new markets
+    pub fn update_max_price_age(ctx: Context<UpdateHouse>, max_price_age: u64) -> Result<()> {
+        require_gt!(max_price_age, 0);
+
+        ctx.accounts.house.max_price_age = max_price_age;
+
+        Ok(())
+    }
+
+    /// cancel_window of 0 disables user cancellation
+    pub fn update_cancel_settings(
+        ctx: Context<UpdateHouse>,
+        cancel_window: i64,
+        cancel_fee_basis_points: u16,
+    ) -> Result<()> {
+        require_gte!(cancel_window, 0);
+        require_gte!(BASIS_POINTS_DIVISOR, cancel_fee_basis_points as u128);
+
+        ctx.accounts.house.cancel_window = cancel_window;
+        ctx.accounts.house.cancel_fee_basis_points = cancel_fee_basis_points;
+
+        Ok(())
+    }
+
+    pub fn update_cash_out_spread(
+        ctx: Context<UpdateHouse>,
+        cash_out_spread_basis_points: u16,
+    ) -> Result<()> {
+        require_gte!(BASIS_POINTS_DIVISOR, cash_out_spread_basis_points as u128);
+
+        ctx.accounts.house.cash_out_spread_basis_points = cash_out_spread_basis_points;
+
+        Ok(())
+    }
+
+    /// max_strike_offset_basis_points of 0 only allows at-the-money strikes
+    pub fn update_strike_settings(
+        ctx: Context<UpdateHouse>,
+        max_strike_offset_basis_points: u16,
+        strike_multiplier_step: u64,
+    ) -> Result<()> {
+        require_gt!(BASIS_POINTS_DIVISOR, max_strike_offset_basis_points as u128);
+
+        ctx.accounts.house.max_strike_offset_basis_points = max_strike_offset_basis_points;
+        ctx.accounts.house.strike_multiplier_step = strike_multiplier_step;
+
+        Ok(())
+    }
+
+    /// range multipliers of 0 disable the matching range bet kind
+    pub fn update_range_settings(
+        ctx: Context<UpdateHouse>,
+        range_basis_points: u16,
+        range_in_multiplier: u64,
+        range_out_multiplier: u64,
+    ) -> Result<()> {
+        require_gt!(range_basis_points, 0);
+        require_gt!(BASIS_POINTS_DIVISOR, range_basis_points as u128);
+
+        ctx.accounts.house.range_basis_points = range_basis_points;
+        ctx.accounts.house.range_in_multiplier = range_in_multiplier;
+        ctx.accounts.house.range_out_multiplier = range_out_multiplier;
+
+        Ok(())
+    }
+
+    /// touch multipliers of 0 disable the matching touch bet kind
+    pub fn update_touch_settings(
+        ctx: Context<UpdateHouse>,
+        touch_barrier_basis_points: u16,
+        touch_multiplier: u64,
+        no_touch_multiplier: u64,
+    ) -> Result<()> {
+        require_gt!(touch_barrier_basis_points, 0);
+        require_gt!(BASIS_POINTS_DIVISOR, touch_barrier_basis_points as u128);
+
+        ctx.accounts.house.touch_barrier_basis_points = touch_barrier_basis_points;
+        ctx.accounts.house.touch_multiplier = touch_multiplier;
+        ctx.accounts.house.no_touch_multiplier = no_touch_multiplier;
+
+        Ok(())
+    }
+
+    /// dispute_window of 0 makes admin settlement prices final immediately
+    pub fn update_dispute_window(ctx: Context<UpdateHouse>, dispute_window: i64) -> Result<()> {
+        require_gte!(dispute_window, 0);
+
+        ctx.accounts.house.dispute_window = dispute_window;
+
+        Ok(())
+    }
+
+    /// settlement_deadline of 0 disables refunds of unsettled bets
+    pub fn update_settlement_deadline(
+        ctx: Context<UpdateHouse>,
+        settlement_deadline: i64,
+    ) -> Result<()> {
+        require_gte!(settlement_deadline, 0);
+
+        ctx.accounts.house.settlement_deadline = settlement_deadline;
+
+        Ok(())
+    }
+
+    /// lock_period of 0 lets deposits queue for withdrawal right away
+    pub fn update_liquidity_settings(
+        ctx: Context<UpdateHouse>,
+        lock_period: i64,
+        epoch_duration: i64,
+    ) -> Result<()> {
+        require_gte!(lock_period, 0);
+        require_gt!(epoch_duration, 0);
+
+        ctx.accounts.house.lock_period = lock_period;
+        ctx.accounts.house.epoch_duration = epoch_duration;
+
+        Ok(())
+    }
+
+    /// an empty revenue_split sends all profit to the beneficiary, otherwise the weights
+    /// must add up to 100%
+    pub fn update_revenue_split(
+        ctx: Context<UpdateHouse>,
+        revenue_split: Vec<RevenueShare>,
+    ) -> Result<()> {
+        require_gte!(MAX_REVENUE_RECIPIENTS, revenue_split.len());
+        require!(
+            revenue_split.iter().all(|share| share.basis_points > 0),
+            OptnError::InvalidRevenueSplit
+        );
+        if !revenue_split.is_empty() {
+            require_eq!(
+                revenue_split
+                    .iter()
+                    .map(|share| share.basis_points as u128)
+                    .sum::<u128>(),
+                BASIS_POINTS_DIVISOR,
+                OptnError::InvalidRevenueSplit
+            );
+        }
+
+        ctx.accounts.house.revenue_split = [RevenueShare::default(); MAX_REVENUE_RECIPIENTS];
+        ctx.accounts.house.revenue_split[..revenue_split.len()].copy_from_slice(&revenue_split);
+
+        Ok(())
+    }
+
+    /// withdrawal_delay of 0 allows instant withdrawals, increases apply immediately while
+    /// decreases wait out the current delay so they are visible ahead of time
+    pub fn update_withdrawal_delay(ctx: Context<UpdateHouse>, withdrawal_delay: i64) -> Result<()> {
+        require_gte!(withdrawal_delay, 0);
+
+        let current_time = Clock::get()?.unix_timestamp;
+        let current_delay = ctx.accounts.house.withdrawal_delay_at(current_time);
+
+        if withdrawal_delay >= current_delay {
+            ctx.accounts.house.withdrawal_delay = withdrawal_delay;
+            ctx.accounts.house.pending_withdrawal_delay = 0;
+            ctx.accounts.house.withdrawal_delay_effective_at = 0;
+        } else {
+            ctx.accounts.house.withdrawal_delay = current_delay;
+            ctx.accounts.house.pending_withdrawal_delay = withdrawal_delay;
+            ctx.accounts.house.withdrawal_delay_effective_at = current_time + current_delay;
+        }
+
+        Ok(())
+    }
+
+    /// Switchboard markets only take fixed-odds bets priced when placed and settle by admin
+    /// price, pull feeds cannot prove which update came first after a timestamp
+    pub fn create_market(
+        ctx: Context<CreateMarket>,
+        feed_id: String,
+        mode: MarketMode,
+        price_source: PriceSource,
+        switchboard_program_id: Pubkey,
+    ) -> Result<()> {
+        let feed_id = get_feed_id_from_hex(&feed_id)?;
+
+        // validate the Switchboard program is the mainnet or devnet deployment
+        require!(
+            switchboard_program_id == ON_DEMAND_MAINNET_PID
+                || switchboard_program_id == ON_DEMAND_DEVNET_PID,
+            OptnError::InvalidPriceAccount
+        );
+
+        ctx.accounts.market.set_inner(Market {
+            house: ctx.accounts.house.key(),
+            mode,
+            status: MarketStatus::Active,
+            reserved_liquidity: 0,
+            total_wagered: 0,
+            active_bets: 0,
+            settled_bets: 0,
+            canceled_bets: 0,
+            open_bets: 0,
+            active_rounds: 0,
+            unclaimed_payouts: 0,
+            volatility_basis_points: 0,
+            expiry_interval: 0,
+            entry_window: 0,
+            max_price_age: ctx.accounts.house.max_price_age,
+            reference_conf_basis_points: 0,
+            open_time: 0,
+            close_time: 0,
+            trading_days: ALL_TRADING_DAYS,
+            blackout_windows: [BlackoutWindow::default(); MAX_BLACKOUT_WINDOWS],
+            decimals: load_price(
+                price_source,
+                &feed_id,
+                &ctx.accounts.price_update,
+                &switchboard_program_id,
+            )?
+            .no_older_than(&Clock::get()?, ctx.accounts.house.max_price_age)?
+            .exponent
+            .abs()
+            .try_into()
+            .unwrap(),
+            price_source,
+            price_update: ctx.accounts.price_update.key(),
+            switchboard_program_id,
+            feed_id,
+            secondary_price_source: PriceSource::Pyth,
+            secondary_price_update: None,
+            secondary_feed_id: [0; 32],
+            max_deviation_basis_points: 0,
+        });
+
+        ctx.accounts.market.validate_price_order()?;
+
+        ctx.accounts.house.active_markets += 1;
+
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        Ok(())
+    }
+
+    /// reduce-only and closed markets stop taking new bets and rounds but keep settling
+    /// the open ones
+    pub fn update_market_status(ctx: Context<UpdateMarket>, status: MarketStatus) -> Result<()> {
+        ctx.accounts.market.status = status;
+
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+
+        Ok(())
+    }
+
+    /// close a retired market and return its rent, house stats are left untouched
+    pub fn close_market(ctx: Context<CloseMarket>) -> Result<()> {
+        // validate market status
+        require!(
+            ctx.accounts.market.status == MarketStatus::Closed,
+            OptnError::MarketNotClosed
+        );
+
+        // validate nothing is left to settle or claim
+        require_eq!(
+            ctx.accounts.market.active_bets,
+            0,
+            OptnError::MarketHasActiveBets
+        );
+        require_eq!(
+            ctx.accounts.market.open_bets,
+            0,
+            OptnError::MarketHasActiveBets
+        );
+        require_eq!(
+            ctx.accounts.market.active_rounds,
+            0,
+            OptnError::MarketHasActiveBets
+        );
+        require_eq!(
+            ctx.accounts.market.unclaimed_payouts,
+            0,
+            OptnError::MarketHasActiveBets
+        );
+
+        ctx.accounts.house.active_markets -= 1;
+
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        Ok(())
+    }
+
+    /// annualized volatility used to price cash-outs, 0 disables cash-out
+    pub fn update_market_volatility(
+        ctx: Context<UpdateMarket>,
+        volatility_basis_points: u32,
+    ) -> Result<()> {
+        ctx.accounts.market.volatility_basis_points = volatility_basis_points;
+
+        Ok(())
+    }
+
+    pub fn update_market_max_price_age(
+        ctx: Context<UpdateMarket>,
+        max_price_age: u64,
+    ) -> Result<()> {
+        require_gt!(max_price_age, 0);
+
+        ctx.accounts.market.max_price_age = max_price_age;
+
+        Ok(())
+    }
+
+    /// Only admin can rotate the price update account, changing the feed or oracle requires
+    /// no active bets or rounds and takes on the new feed's decimals
+    pub fn update_price_update(
+        ctx: Context<UpdatePriceUpdate>,
+        feed_id: Option<String>,
+        price_source: Option<PriceSource>,
+    ) -> Result<()> {
+        let feed_id = match feed_id {
+            Some(feed_id) => get_feed_id_from_hex(&feed_id)?,
+            None => ctx.accounts.market.feed_id,
+        };
+        let price_source = price_source.unwrap_or(ctx.accounts.market.price_source);
+        let feed_changed = feed_id != ctx.accounts.market.feed_id
+            || price_source != ctx.accounts.market.price_source;
+        if feed_changed {
+            require_eq!(
+                ctx.accounts.market.active_bets,
+                0,
+                OptnError::MarketHasActiveBets
+            );
+            require_eq!(
+                ctx.accounts.market.active_rounds,
+                0,
+                OptnError::MarketHasActiveBets
+            );
+        }
+
+        // validate the new account carries the feed, at the same precision unless the feed or
+        // oracle changed
+        let decimals: u8 = load_price(
+            price_source,
+            &feed_id,
+            &ctx.accounts.price_update,
+            &ctx.accounts.market.switchboard_program_id,
+        )?
+        .no_older_than(&Clock::get()?, ctx.accounts.market.max_price_age)?
+        .exponent
+        .abs()
+        .try_into()
+        .unwrap();
+        if feed_changed {
+            ctx.accounts.market.decimals = decimals;
+        } else {
+            require_eq!(
+                decimals,
+                ctx.accounts.market.decimals,
+                OptnError::OracleMismatch
+            );
+        }
+
+        ctx.accounts.market.price_source = price_source;
+        ctx.accounts.market.price_update = ctx.accounts.price_update.key();
+        ctx.accounts.market.feed_id = feed_id;
+        ctx.accounts.market.validate_price_order()?;
+
+        emit_market_oracle_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+
+        Ok(())
+    }
+
+    /// Only admin can configure the secondary oracle, omitting the account disables the cross-check
+    pub fn update_secondary_oracle(
+        ctx: Context<UpdateSecondaryOracle>,
+        price_source: PriceSource,
+        feed_id: String,
+        max_deviation_basis_points: u16,
+    ) -> Result<()> {
+        let feed_id = get_feed_id_from_hex(&feed_id)?;
+
+        match &ctx.accounts.secondary_price_update {
+            Some(secondary_price_update) => {
+                // validate the account carries the feed
+                load_price(
+                    price_source,
+                    &feed_id,
+                    secondary_price_update,
+                    &ctx.accounts.market.switchboard_program_id,
+                )?
+                .no_older_than(&Clock::get()?, ctx.accounts.market.max_price_age)?;
+
+                ctx.accounts.market.secondary_price_update = Some(secondary_price_update.key());
+            }
+            None => ctx.accounts.market.secondary_price_update = None,
+        }
+        ctx.accounts.market.secondary_price_source = price_source;
+        ctx.accounts.market.secondary_feed_id = feed_id;
+        ctx.accounts.market.max_deviation_basis_points = max_deviation_basis_points;
+        ctx.accounts.market.validate_price_order()?;
+
+        emit_market_oracle_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+
+        Ok(())
+    }
+
+    /// reference_conf_basis_points of 0 disables volatility-adjusted payouts
+    pub fn update_reference_conf(
+        ctx: Context<UpdateMarket>,
+        reference_conf_basis_points: u16,
+    ) -> Result<()> {
+        ctx.accounts.market.reference_conf_basis_points = reference_conf_basis_points;
+
+        Ok(())
+    }
+
+    /// open_time and close_time are seconds after UTC midnight, equal times trade all day,
+    /// a close before the open runs the session overnight
+    pub fn update_trading_hours(
+        ctx: Context<UpdateMarket>,
+        open_time: u32,
+        close_time: u32,
+        trading_days: u8,
+    ) -> Result<()> {
+        require_gt!(SECONDS_PER_DAY, open_time as i64);
+        require_gt!(SECONDS_PER_DAY, close_time as i64);
+        require_eq!(trading_days & !ALL_TRADING_DAYS, 0);
+
+        ctx.accounts.market.open_time = open_time;
+        ctx.accounts.market.close_time = close_time;
+        ctx.accounts.market.trading_days = trading_days;
+
+        Ok(())
+    }
+
+    /// an empty window (start == end) clears the slot
+    pub fn update_blackout_window(
+        ctx: Context<UpdateMarket>,
+        index: u8,
+        start: i64,
+        end: i64,
+    ) -> Result<()> {
+        require_gt!(MAX_BLACKOUT_WINDOWS, index as usize);
+        require_gte!(end, start);
+
+        ctx.accounts.market.blackout_windows[index as usize] = BlackoutWindow { start, end };
+
+        Ok(())
+    }
+
+    /// expiry_interval of 0 disables expiry alignment
+    pub fn update_expiry_interval(ctx: Context<UpdateMarket>, expiry_interval: i64) -> Result<()> {
+        require_gte!(expiry_interval, 0);
+
+        ctx.accounts.market.expiry_interval = expiry_interval;
+
+        Ok(())
+    }
+
+    /// entry_window of 0 reads the entry price when the bet is created, otherwise bets wait
+    /// for the first verified update published after their creation
+    pub fn update_entry_window(ctx: Context<UpdateMarket>, entry_window: i64) -> Result<()> {
+        require_gte!(entry_window, 0);
+
+        ctx.accounts.market.entry_window = entry_window;
+        ctx.accounts.market.validate_price_order()?;
+
+        Ok(())
+    }
+
+    /// Only admin can schedule a round on a parimutuel market
+    pub fn create_round(ctx: Context<CreateRound>, lock_time: i64, close_time: i64) -> Result<()> {
+        // validate market mode
+        require!(
+            ctx.accounts.market.mode == MarketMode::Parimutuel,
+            OptnError::MarketModeMismatch
+        );
+
+        // validate market status
+        require!(
+            ctx.accounts.market.status == MarketStatus::Active,
+            OptnError::MarketInactive
+        );
+
+        // validate schedule
+        require_gt!(lock_time, Clock::get()?.unix_timestamp);
+        require_gt!(close_time, lock_time);
+
+        ctx.accounts.round.set_inner(Round {
+            market: ctx.accounts.market.key(),
+            buy_pool: 0,
+            sell_pool: 0,
+            fee_amount: 0,
+            lock_price: 0,
+            close_price: 0,
+            status: RoundStatus::Open,
+            active_positions: 0,
+            unclaimed_payouts: 0,
+            lock_time,
+            close_time,
+        });
+
+        ctx.accounts.market.active_rounds += 1;
+
+        emit_round_updated(&ctx.accounts.round.key(), &ctx.accounts.round);
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+
+        Ok(())
+    }
+
+    /// anyone can wager into an open round until its lock time
+    pub fn enter_round(
+        ctx: Context<EnterRound>,
+        direction: Direction,
+        lamports: u64,
+    ) -> Result<()> {
+        // validate market status
+        require!(
+            ctx.accounts.market.status == MarketStatus::Active,
+            OptnError::MarketInactive
+        );
+
+        // validate wager amount
+        require_gte!(lamports, ctx.accounts.house.min_wager, OptnError::MinWager);
+        require_gte!(ctx.accounts.house.max_wager, lamports, OptnError::MaxWager);
+
+        // validate round
+        require!(
+            ctx.accounts.round.status == RoundStatus::Open,
+            OptnError::RoundLocked
+        );
+        require_gt!(
+            ctx.accounts.round.lock_time,
+            Clock::get()?.unix_timestamp,
+            OptnError::RoundLocked
+        );
+
+        match direction {
+            Direction::Buy => ctx.accounts.round.buy_pool += lamports,
+            Direction::Sell => ctx.accounts.round.sell_pool += lamports,
+        }
+        ctx.accounts.round.active_positions += 1;
+
+        ctx.accounts.market.total_wagered += lamports;
+        ctx.accounts.house.total_wagered += lamports;
+
+        ctx.accounts.position.set_inner(Position {
+            round: ctx.accounts.round.key(),
+            authority: ctx.accounts.user.key(),
+            direction,
+            amount: lamports,
+        });
+
+        emit_round_updated(&ctx.accounts.round.key(), &ctx.accounts.round);
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.house_authority.to_account_info(),
+                },
+            ),
+            lamports,
+        )
+    }
+
+    /// anyone can record the lock price from the first verified price update published at or
+    /// after lock time
+    pub fn start_round(ctx: Context<UpdateRound>) -> Result<()> {
+        // validate round
+        require!(
+            ctx.accounts.round.status == RoundStatus::Open,
+            OptnError::RoundLocked
+        );
+        require_gte!(
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.round.lock_time,
+            OptnError::RoundNotReady
+        );
+
+        // validate observation
+        let price = ctx.accounts.market.load_price(&ctx.accounts.price_update)?;
+        require!(
+            price.is_first_at_or_after(ctx.accounts.round.lock_time),
+            OptnError::NotFirstPrice
+        );
+        ctx.accounts.market.cross_check_at(
+            &price,
+            ctx.accounts.secondary_price_update.as_deref(),
+            ctx.accounts.round.lock_time,
+        )?;
+        require_gt!(
+            ctx.accounts.round.close_time,
+            price.publish_time,
+            OptnError::PriceOutsideRoundWindow
+        );
+
+        ctx.accounts.round.lock_price = price.price.try_into().unwrap();
+        ctx.accounts.round.status = RoundStatus::Locked;
+
+        emit_round_updated(&ctx.accounts.round.key(), &ctx.accounts.round);
+
+        Ok(())
+    }
+
+    /// anyone can record the close price from the first verified price update published at or
+    /// after close time and resolve the round, rounds the feed published no lock price for or
+    /// with no counterparty are canceled and refunded
+    pub fn end_round(ctx: Context<UpdateRound>) -> Result<()> {
+        // validate round
+        require!(
+            ctx.accounts.round.status == RoundStatus::Open
+                || ctx.accounts.round.status == RoundStatus::Locked,
+            OptnError::RoundSettled
+        );
+        require_gte!(
+            Clock::get()?.unix_timestamp,
+            ctx.accounts.round.close_time,
+            OptnError::RoundNotReady
+        );
+
+        let price = ctx.accounts.market.load_price(&ctx.accounts.price_update)?;
+        if ctx.accounts.round.status == RoundStatus::Open {
+            // validate the feed published nothing between lock and close time
+            require!(
+                price.is_first_at_or_after(ctx.accounts.round.lock_time),
+                OptnError::NotFirstPrice
+            );
+            require_gte!(
+                price.publish_time,
+                ctx.accounts.round.close_time,
+                OptnError::PriceOutsideRoundWindow
+            );
+        } else {
+            // validate observation
+            require!(
+                price.is_first_at_or_after(ctx.accounts.round.close_time),
+                OptnError::NotFirstPrice
+            );
+            ctx.accounts.market.cross_check_at(
+                &price,
+                ctx.accounts.secondary_price_update.as_deref(),
+                ctx.accounts.round.close_time,
+            )?;
+
+            ctx.accounts.round.close_price = price.price.try_into().unwrap();
+        }
+
+        let total_pool = ctx.accounts.round.buy_pool + ctx.accounts.round.sell_pool;
+
+        if ctx.accounts.round.winning_pool() > 0 && total_pool > ctx.accounts.round.winning_pool() {
+            ctx.accounts.round.status = RoundStatus::Ended;
+            ctx.accounts.round.fee_amount = u64::try_from(
+                total_pool as u128 * ctx.accounts.house.fee_basis_points as u128
+                    / BASIS_POINTS_DIVISOR,
+            )
+            .unwrap();
+
+            ctx.accounts.house.total_profit += ctx.accounts.round.fee_amount;
+        } else {
+            ctx.accounts.round.status = RoundStatus::Canceled;
+        }
+
+        ctx.accounts.market.total_wagered -= total_pool;
+        ctx.accounts.house.total_wagered -= total_pool;
+
+        ctx.accounts.round.unclaimed_payouts = total_pool - ctx.accounts.round.fee_amount;
+        ctx.accounts.market.unclaimed_payouts += ctx.accounts.round.unclaimed_payouts;
+        ctx.accounts.house.unclaimed_payouts += ctx.accounts.round.unclaimed_payouts;
+
+        // a round nobody entered has nothing left to claim
+        if ctx.accounts.round.active_positions == 0 {
+            ctx.accounts.market.active_rounds -= 1;
+        }
+
+        emit_round_updated(&ctx.accounts.round.key(), &ctx.accounts.round);
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        Ok(())
+    }
+
+    /// close position account and pay out its share of the round back to the user
+    pub fn claim_round(ctx: Context<ClaimRound>) -> Result<()> {
+        // validate status
+        require!(
+            ctx.accounts.round.status == RoundStatus::Ended
+                || ctx.accounts.round.status == RoundStatus::Canceled,
+            OptnError::RoundPending
+        );
+
+        let payout = ctx.accounts.round.payout(&ctx.accounts.position);
+
+        ctx.accounts.round.active_positions -= 1;
+        ctx.accounts.round.unclaimed_payouts -= payout;
+        ctx.accounts.market.unclaimed_payouts -= payout;
+        ctx.accounts.house.unclaimed_payouts -= payout;
+        if ctx.accounts.round.active_positions == 0 {
+            // rounding dust left after the last claim goes to the house
+            ctx.accounts.market.unclaimed_payouts -= ctx.accounts.round.unclaimed_payouts;
+            ctx.accounts.house.unclaimed_payouts -= ctx.accounts.round.unclaimed_payouts;
+            ctx.accounts.house.total_profit += ctx.accounts.round.unclaimed_payouts;
+            ctx.accounts.round.unclaimed_payouts = 0;
+
+            ctx.accounts.market.active_rounds -= 1;
+        }
+
+        emit_round_updated(&ctx.accounts.round.key(), &ctx.accounts.round);
+        emit_market_updated(&ctx.accounts.market.key(), &ctx.accounts.market);
+        emit_house_updated(&ctx.accounts.house.key(), &ctx.accounts.house);
+
+        if payout > 0 {
+            validate_rent_floor(&ctx.accounts.house_authority, payout)?;
+
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.house_authority.to_account_info(),
+                        to: ctx.accounts.authority.to_account_info(),
+                    },
+                )
+                .with_signer(&[&[
+                    HOUSE_AUTHORITY_PREFIX,
+                    &ctx.accounts.house.key().to_bytes(),
+                    &[ctx.accounts.house.authority_bump],
+                ]]),
+                payout,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CreateBet<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(zero)]
+    pub bet: Account<'info, Bet>,
+
+    /// CHECK: validated by has_one on market and parsed per market price source
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: validated against market.secondary_price_update and parsed per its price source
+    pub secondary_price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(mut, has_one = house, has_one = price_update)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+
+    /// CHECK: OK
+    #[account(mut,
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump = house.authority_bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ActivateBet<'info> {
+    #[account(mut, has_one = market)]
+    pub bet: Account<'info, Bet>,
+
+    /// CHECK: any account of the market price source, owner, feed and verification are
+    /// validated when parsed, historical updates live outside the market price account
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: any account of the secondary price source, validated when parsed like
+    /// price_update
+    pub secondary_price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBet<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = market, has_one = authority, close = authority)]
+    pub bet: Account<'info, Bet>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+
+    /// CHECK: OK
+    #[account(mut,
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump = house.authority_bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundBet<'info> {
+    /// CHECK: bettor receiving the refund, validated by has_one on bet
+    #[account(mut)]
+    pub authority: UncheckedAccount<'info>,
+
+    #[account(mut, has_one = market, has_one = authority, close = authority)]
+    pub bet: Account<'info, Bet>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+
+    /// CHECK: OK
+    #[account(mut,
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump = house.authority_bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CashOut<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = market, has_one = authority, close = authority)]
+    pub bet: Account<'info, Bet>,
+
+    /// CHECK: validated by has_one on market and parsed per market price source
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: validated against market.secondary_price_update and parsed per its price source
+    pub secondary_price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(mut, has_one = house, has_one = price_update)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+
+    /// CHECK: OK
+    #[account(mut,
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump = house.authority_bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordPrice<'info> {
+    #[account(mut, has_one = market)]
+    pub bet: Account<'info, Bet>,
+
+    /// CHECK: validated by has_one on market and parsed per market price source
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: validated against market.secondary_price_update and parsed per its price source
+    pub secondary_price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(has_one = price_update)]
+    pub market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct SettleBet<'info> {
+    #[account(mut, has_one = market)]
+    pub bet: Account<'info, Bet>,
+
+    #[account(
+        seeds = [SETTLEMENT_PRICE_PREFIX, &market.key().to_bytes(), &bet.expires_at.to_le_bytes()],
+        bump = settlement_price.bump,
+    )]
+    pub settlement_price: Account<'info, SettlementPrice>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+#[instruction(timestamp: i64)]
+pub struct RecordSettlementPrice<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(init,
+        payer = payer,
+        space = 8 + SettlementPrice::INIT_SPACE,
+        seeds = [SETTLEMENT_PRICE_PREFIX, &market.key().to_bytes(), &timestamp.to_le_bytes()],
+        bump,
+    )]
+    pub settlement_price: Account<'info, SettlementPrice>,
+
+    /// CHECK: any account of the market price source, owner, feed and verification are
+    /// validated when parsed, historical updates live outside the market price account
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: any account of the secondary price source, validated when parsed like
+    /// price_update
+    pub secondary_price_update: Option<UncheckedAccount<'info>>,
+
+    pub market: Account<'info, Market>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(timestamp: i64)]
+pub struct SetSettlementPrice<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(init,
+        payer = admin,
+        space = 8 + SettlementPrice::INIT_SPACE,
+        seeds = [SETTLEMENT_PRICE_PREFIX, &market.key().to_bytes(), &timestamp.to_le_bytes()],
+        bump,
+    )]
+    pub settlement_price: Account<'info, SettlementPrice>,
+
+    #[account(has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(has_one = admin)]
+    pub house: Account<'info, House>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeSettlementPrice<'info> {
+    #[account(mut,
+        has_one = market,
+        seeds = [SETTLEMENT_PRICE_PREFIX, &market.key().to_bytes(), &settlement_price.timestamp.to_le_bytes()],
+        bump = settlement_price.bump,
+    )]
+    pub settlement_price: Account<'info, SettlementPrice>,
+
+    /// CHECK: any account of the market price source, owner, feed and verification are
+    /// validated when parsed, historical updates live outside the market price account
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: any account of the secondary price source, validated when parsed like
+    /// price_update
+    pub secondary_price_update: Option<UncheckedAccount<'info>>,
+
+    pub market: Account<'info, Market>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBet<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = market, has_one = authority, close = authority)]
+    pub bet: Account<'info, Bet>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+
+    /// CHECK: OK
+    #[account(mut,
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump = house.authority_bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositLiquidity<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(mut,
+        has_one = house,
+        seeds = [LIQUIDITY_POSITION_PREFIX, &house.key().to_bytes(), &payer.key().to_bytes()],
+        bump = liquidity_position.bump,
+    )]
+    pub liquidity_position: Account<'info, LiquidityPosition>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+
+    /// CHECK: OK
+    #[account(mut,
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump = house.authority_bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
 
-    pub fn update_wager_limits(
-        ctx: Context<UpdateHouse>,
-        min_wager: u64,
-        max_wager: u64,
-    ) -> Result<()> {
-        require_gt!(min_wager, 0);
-        require_gt!(max_wager, min_wager);
+#[derive(Accounts)]
+pub struct CreateLiquidityPosition<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
-        ctx.accounts.house.min_wager = min_wager;
-        ctx.accounts.house.max_wager = max_wager;
+    pub house: Account<'info, House>,
 
-        Ok(())
-    }
+    #[account(init,
+        payer = authority,
+        space = 8 + LiquidityPosition::INIT_SPACE,
+        seeds = [LIQUIDITY_POSITION_PREFIX, &house.key().to_bytes(), &authority.key().to_bytes()],
+        bump,
+    )]
+    pub liquidity_position: Account<'info, LiquidityPosition>,
 
-    pub fn create_market(ctx: Context<CreateMarket>, feed_id: String) -> Result<()> {
-        ctx.accounts.market.set_inner(Market {
-            house: ctx.accounts.house.key(),
-            reserved_liquidity: 0,
-            total_wagered: 0,
-            active_bets: 0,
-            settled_bets: 0,
-            canceled_bets: 0,
-            decimals: ctx
-                .accounts
-                .price_update
-                .get_price_no_older_than(
-                    &Clock::get()?,
-                    PRICE_MAXIMUM_AGE,
-                    &get_feed_id_from_hex(&feed_id[..])?,
-                )?
-                .exponent
-                .abs()
-                .try_into()
-                .unwrap(),
-            price_update: ctx.accounts.price_update.key(),
-            feed_id,
-        });
+    pub system_program: Program<'info, System>,
+}
 
-        Ok(())
-    }
+#[derive(Accounts)]
+pub struct QueueLiquidityWithdrawal<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        has_one = house,
+        has_one = authority,
+        seeds = [LIQUIDITY_POSITION_PREFIX, &house.key().to_bytes(), &authority.key().to_bytes()],
+        bump = liquidity_position.bump,
+    )]
+    pub liquidity_position: Account<'info, LiquidityPosition>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
 }
 
 #[derive(Accounts)]
-pub struct CreateBet<'info> {
+pub struct AdvanceEpoch<'info> {
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub payer: Signer<'info>,
 
-    #[account(zero)]
-    pub bet: Account<'info, Bet>,
+    #[account(mut)]
+    pub house: Account<'info, House>,
 
-    pub price_update: Account<'info, PriceUpdateV2>,
+    #[account(init,
+        payer = payer,
+        space = 8 + Epoch::INIT_SPACE,
+        seeds = [EPOCH_PREFIX, &house.key().to_bytes(), &house.epoch.to_le_bytes()],
+        bump,
+    )]
+    pub epoch: Account<'info, Epoch>,
 
-    #[account(mut, has_one = house, has_one = price_update)]
-    pub market: Account<'info, Market>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimLiquidityWithdrawal<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut,
+        has_one = house,
+        has_one = authority,
+        seeds = [LIQUIDITY_POSITION_PREFIX, &house.key().to_bytes(), &authority.key().to_bytes()],
+        bump = liquidity_position.bump,
+    )]
+    pub liquidity_position: Account<'info, LiquidityPosition>,
+
+    #[account(mut,
+        has_one = house,
+        seeds = [EPOCH_PREFIX, &house.key().to_bytes(), &liquidity_position.withdrawal_epoch.to_le_bytes()],
+        bump = epoch.bump,
+    )]
+    pub epoch: Account<'info, Epoch>,
 
     #[account(mut)]
     pub house: Account<'info, House>,
@@ -369,31 +2554,44 @@ pub struct CreateBet<'info> {
 }
 
 #[derive(Accounts)]
-pub struct SettleBet<'info> {
-    pub admin: Signer<'info>,
+pub struct ClaimClosedLiquidityWithdrawal<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
-    #[account(mut, has_one = market)]
-    pub bet: Account<'info, Bet>,
+    #[account(mut,
+        has_one = house,
+        has_one = authority,
+        seeds = [LIQUIDITY_POSITION_PREFIX, &house.key().to_bytes(), &authority.key().to_bytes()],
+        bump = liquidity_position.bump,
+    )]
+    pub liquidity_position: Account<'info, LiquidityPosition>,
 
-    #[account(mut, has_one = house)]
-    pub market: Account<'info, Market>,
+    #[account(mut,
+        has_one = house,
+        seeds = [EPOCH_PREFIX, &house.key().to_bytes(), &epoch.epoch.to_le_bytes()],
+        bump = epoch.bump,
+    )]
+    pub epoch: Account<'info, Epoch>,
 
-    #[account(mut, has_one = admin)]
-    pub house: Account<'info, House>,
+    /// CHECK: the closed house, validated empty
+    pub house: UncheckedAccount<'info>,
+
+    /// CHECK: OK
+    #[account(mut,
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CloseBet<'info> {
+pub struct WithdrawLiquidity<'info> {
     #[account(mut)]
-    pub authority: Signer<'info>,
-
-    #[account(mut, has_one = market, has_one = authority, close = authority)]
-    pub bet: Account<'info, Bet>,
-
-    #[account(mut, has_one = house)]
-    pub market: Account<'info, Market>,
+    pub beneficiary: Signer<'info>,
 
-    #[account(mut)]
+    #[account(mut, has_one = beneficiary)]
     pub house: Account<'info, House>,
 
     /// CHECK: OK
@@ -407,13 +2605,40 @@ pub struct CloseBet<'info> {
 }
 
 #[derive(Accounts)]
-pub struct DepositLiquidity<'info> {
+pub struct RequestWithdrawal<'info> {
     #[account(mut)]
-    pub payer: Signer<'info>,
+    pub beneficiary: Signer<'info>,
+
+    #[account(has_one = beneficiary)]
+    pub house: Account<'info, House>,
+
+    #[account(init,
+        payer = beneficiary,
+        space = 8 + WithdrawalRequest::INIT_SPACE,
+        seeds = [WITHDRAWAL_REQUEST_PREFIX, &house.key().to_bytes()],
+        bump,
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    pub system_program: Program<'info, System>,
+}
 
+#[derive(Accounts)]
+pub struct ExecuteWithdrawal<'info> {
     #[account(mut)]
+    pub beneficiary: Signer<'info>,
+
+    #[account(mut, has_one = beneficiary)]
     pub house: Account<'info, House>,
 
+    #[account(mut,
+        has_one = house,
+        close = beneficiary,
+        seeds = [WITHDRAWAL_REQUEST_PREFIX, &house.key().to_bytes()],
+        bump = withdrawal_request.bump,
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
     /// CHECK: OK
     #[account(mut,
         seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
@@ -425,13 +2650,54 @@ pub struct DepositLiquidity<'info> {
 }
 
 #[derive(Accounts)]
-pub struct WithdrawLiquidity<'info> {
+pub struct CancelWithdrawal<'info> {
     #[account(mut)]
     pub beneficiary: Signer<'info>,
 
-    #[account(mut, has_one = beneficiary)]
+    #[account(has_one = beneficiary)]
+    pub house: Account<'info, House>,
+
+    #[account(mut,
+        has_one = house,
+        close = beneficiary,
+        seeds = [WITHDRAWAL_REQUEST_PREFIX, &house.key().to_bytes()],
+        bump = withdrawal_request.bump,
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+}
+
+#[derive(Accounts)]
+pub struct AuditHouse<'info> {
+    pub house: Account<'info, House>,
+
+    /// CHECK: OK
+    #[account(
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump = house.authority_bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseHouse<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// CHECK: validated by has_one on house
+    #[account(mut)]
+    pub beneficiary: UncheckedAccount<'info>,
+
+    #[account(mut, has_one = admin, has_one = beneficiary, close = admin)]
     pub house: Account<'info, House>,
 
+    #[account(init,
+        payer = admin,
+        space = 8 + Epoch::INIT_SPACE,
+        seeds = [EPOCH_PREFIX, &house.key().to_bytes(), &house.epoch.to_le_bytes()],
+        bump,
+    )]
+    pub epoch: Account<'info, Epoch>,
+
     /// CHECK: OK
     #[account(mut,
         seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
@@ -456,6 +2722,8 @@ pub struct CreateHouse<'info> {
         bump,
     )]
     pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -470,15 +2738,159 @@ pub struct UpdateHouse<'info> {
 #[derive(Accounts)]
 pub struct CreateMarket<'info> {
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub admin: Signer<'info>,
+
+    /// CHECK: parsed per price source
+    pub price_update: UncheckedAccount<'info>,
+
+    #[account(zero)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut, has_one = admin)]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarket<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(has_one = admin)]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMarket<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(mut, has_one = house, close = admin)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut, has_one = admin)]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceUpdate<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// CHECK: parsed per price source
+    pub price_update: UncheckedAccount<'info>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(has_one = admin)]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateSecondaryOracle<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// CHECK: parsed per price source
+    pub secondary_price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(has_one = admin)]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct CreateRound<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(zero)]
+    pub round: Account<'info, Round>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(has_one = admin)]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct EnterRound<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(zero)]
+    pub position: Account<'info, Position>,
+
+    #[account(mut, has_one = market)]
+    pub round: Account<'info, Round>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+
+    /// CHECK: OK
+    #[account(mut,
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump = house.authority_bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRound<'info> {
+    #[account(mut, has_one = market)]
+    pub round: Account<'info, Round>,
+
+    /// CHECK: any account of the market price source, owner, feed and verification are
+    /// validated when parsed, historical updates live outside the market price account
+    pub price_update: UncheckedAccount<'info>,
+
+    /// CHECK: any account of the secondary price source, validated when parsed like
+    /// price_update
+    pub secondary_price_update: Option<UncheckedAccount<'info>>,
+
+    #[account(mut, has_one = house)]
+    pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub house: Account<'info, House>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRound<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
 
-    pub price_update: Account<'info, PriceUpdateV2>,
+    #[account(mut, has_one = round, has_one = authority, close = authority)]
+    pub position: Account<'info, Position>,
 
-    #[account(zero)]
+    #[account(mut, has_one = market)]
+    pub round: Account<'info, Round>,
+
+    #[account(mut, has_one = house)]
     pub market: Account<'info, Market>,
 
-    #[account(has_one = admin)]
+    #[account(mut)]
     pub house: Account<'info, House>,
+
+    /// CHECK: OK
+    #[account(mut,
+        seeds = [HOUSE_AUTHORITY_PREFIX, &house.key().to_bytes()],
+        bump = house.authority_bump,
+    )]
+    pub house_authority: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[account]
@@ -502,9 +2914,17 @@ pub struct House {
     pub total_profit: u64, // Profits from lost bets & fees
     pub claimed_profits: u64, // Profits withdrawn by the beneficiary
 
+    /// Payouts owed on settled bets, ended rounds and redeemed shares not yet claimed
+    pub unclaimed_payouts: u64,
+    /// Part of unclaimed_payouts owed for redeemed shares, left in the house authority for
+    /// liquidity providers to claim when the house closes
+    pub unclaimed_withdrawals: u64,
+
     pub active_bets: u32,
     pub settled_bets: u32,
     pub canceled_bets: u32,
+    /// Markets created and not yet closed
+    pub active_markets: u32,
 
     pub min_wager: u64,
     pub max_wager: u64,
@@ -516,12 +2936,126 @@ pub struct House {
     pub multiplier: u64,
     pub fee_basis_points: u16,
 
+    /// Default maximum price age in seconds for new markets
+    pub max_price_age: u64,
+
+    /// Seconds after creation a bettor may cancel a pending bet, 0 disables
+    pub cancel_window: i64,
+    pub cancel_fee_basis_points: u16,
+
+    /// House spread taken from the fair value on cash-out
+    pub cash_out_spread_basis_points: u16,
+
+    /// Maximum strike distance from the entry price, 0 only allows at-the-money strikes
+    pub max_strike_offset_basis_points: u16,
+    /// Multiplier change per basis point out of the money, precision: 9 decimal places
+    pub strike_multiplier_step: u64,
+
+    /// Half-width of the band around the entry price for range bets
+    pub range_basis_points: u16,
+    /// Precision: 9 decimal places, 0 disables the bet kind
+    pub range_in_multiplier: u64,
+    pub range_out_multiplier: u64,
+
+    /// Barrier distance from the entry price for touch bets
+    pub touch_barrier_basis_points: u16,
+    /// Precision: 9 decimal places, 0 disables the bet kind
+    pub touch_multiplier: u64,
+    pub no_touch_multiplier: u64,
+
+    /// Seconds an admin settlement price can be disputed, 0 disables disputes
+    pub dispute_window: i64,
+
+    /// Seconds after expiry a pending bet can be refunded by anyone, 0 disables refunds
+    pub settlement_deadline: i64,
+
+    /// Seconds a queued withdrawal waits before it executes, 0 allows instant withdrawals
+    pub withdrawal_delay: i64,
+    /// Lower delay replacing withdrawal_delay once withdrawal_delay_effective_at passes
+    pub pending_withdrawal_delay: i64,
+    /// 0 when no delay decrease is queued
+    pub withdrawal_delay_effective_at: i64,
+
+    /// Shares held by liquidity providers and the beneficiary, including those queued for
+    /// withdrawal
+    pub total_shares: u64,
+    /// Shares backing the beneficiary's liquidity, withdrawn through withdraw_liquidity and
+    /// withdrawal requests rather than epochs
+    pub beneficiary_shares: u64,
+    /// Shares queued for redemption at the end of the current epoch
+    pub pending_withdrawal_shares: u64,
+    /// Lamports per share snapshotted at the end of the last epoch, net of reserved liquidity
+    /// Precision: 9 decimal places
+    pub share_price: u64,
+    /// Seconds deposited liquidity stays locked
+    pub lock_period: i64,
+    pub epoch: u64,
+    pub epoch_started_at: i64,
+    /// Minimum seconds between epoch boundaries
+    pub epoch_duration: i64,
+
+    /// Profit split by weight on claim, unused slots have 0 basis points
+    pub revenue_split: [RevenueShare; MAX_REVENUE_RECIPIENTS],
+
     pub authority_bump: u8,
 }
 
+impl House {
+    /// Withdrawal delay in force at the timestamp, including a queued decrease that took effect
+    pub fn withdrawal_delay_at(&self, timestamp: i64) -> i64 {
+        if self.withdrawal_delay_effective_at > 0 && timestamp >= self.withdrawal_delay_effective_at
+        {
+            self.pending_withdrawal_delay
+        } else {
+            self.withdrawal_delay
+        }
+    }
+
+    /// Hand liquidity left without shares, from before shares existed or rounding dust after
+    /// every provider exited, to the beneficiary at one share per lamport
+    pub fn share_unshared_liquidity(&mut self) {
+        if self.liquidity == 0 && self.total_shares == self.beneficiary_shares {
+            self.beneficiary_shares = 0;
+            self.total_shares = 0;
+        } else if self.total_shares == 0 {
+            self.beneficiary_shares = self.liquidity;
+            self.total_shares = self.liquidity;
+        }
+    }
+
+    /// Liquidity if every open wager is lost to the house, deposits are priced against it so
+    /// new shares never buy into pending bettor losses
+    pub fn max_liquidity(&self) -> u64 {
+        self.liquidity + self.total_wagered
+    }
+
+    /// Liquidity if every open bet pays out, redemptions are priced against it so shares
+    /// never leave ahead of pending bettor wins
+    pub fn min_liquidity(&self) -> u64 {
+        self.liquidity - self.reserved_liquidity
+    }
+
+    /// Shares the lamports buy at the maximum liquidity per share
+    pub fn shares_for(&self, lamports: u64) -> Result<u64> {
+        if self.total_shares == 0 {
+            return Ok(lamports);
+        }
+
+        // validate the shares are still backed, worthless shares are redeemed through epochs
+        require_gt!(self.liquidity, 0, OptnError::LiquidityExhausted);
+
+        Ok(u64::try_from(
+            lamports as u128 * self.total_shares as u128 / self.max_liquidity() as u128,
+        )
+        .unwrap())
+    }
+}
+
 #[account]
 pub struct Market {
     pub house: Pubkey,
+    pub mode: MarketMode,
+    pub status: MarketStatus,
 
     pub reserved_liquidity: u64,
     pub total_wagered: u64,
@@ -529,10 +3063,193 @@ pub struct Market {
     pub active_bets: u32,
     pub settled_bets: u32,
     pub canceled_bets: u32,
+    /// Bet accounts not yet closed, including settled bets awaiting close_bet
+    pub open_bets: u32,
+    /// Rounds still open or holding unclaimed positions
+    pub active_rounds: u32,
+    /// Payouts owed on settled bets and ended rounds not yet claimed
+    pub unclaimed_payouts: u64,
+
+    /// Annualized volatility used to price cash-outs, 0 disables cash-out
+    pub volatility_basis_points: u32,
+    /// Expiries snap up to multiples of this many seconds, 0 disables alignment
+    pub expiry_interval: i64,
+    /// Seconds after creation a delayed entry price may be published, 0 disables delayed entry
+    pub entry_window: i64,
+    /// Maximum price age in seconds
+    pub max_price_age: u64,
+    /// EMA confidence above which payouts scale down, 0 disables the adjustment
+    pub reference_conf_basis_points: u16,
+
+    /// Session open in seconds after UTC midnight, equal to close_time when trading all day
+    pub open_time: u32,
+    /// Session close in seconds after UTC midnight, before open_time for overnight sessions
+    pub close_time: u32,
+    /// Bitmask of weekdays the session opens on, bit 0 is Monday
+    pub trading_days: u8,
+    /// Holidays and maintenance periods no bet may expire in
+    pub blackout_windows: [BlackoutWindow; MAX_BLACKOUT_WINDOWS],
 
     pub decimals: u8,
+    pub price_source: PriceSource,
     pub price_update: Pubkey,
-    pub feed_id: String,
+    /// Switchboard On-Demand deployment, mainnet or devnet, the market's pull feeds belong to
+    pub switchboard_program_id: Pubkey,
+    /// Pyth feed id or Switchboard feed hash, fixed size so changing it never outgrows the
+    /// account the client allocated
+    pub feed_id: [u8; 32],
+
+    /// Optional oracle cross-checked against the primary price
+    pub secondary_price_source: PriceSource,
+    pub secondary_price_update: Option<Pubkey>,
+    pub secondary_feed_id: [u8; 32],
+    pub max_deviation_basis_points: u16,
+}
+
+impl Market {
+    /// Whether every configured oracle can prove an update is the first at or after a
+    /// timestamp, Switchboard pull feeds keep no update history
+    pub fn proves_price_order(&self) -> bool {
+        self.price_source == PriceSource::Pyth
+            && (self.secondary_price_update.is_none()
+                || self.secondary_price_source == PriceSource::Pyth)
+    }
+
+    /// Reject delayed entry and rounds on oracles that cannot prove ordering, their markets
+    /// only take bets priced when placed and settle by admin price
+    pub fn validate_price_order(&self) -> Result<()> {
+        require!(
+            self.proves_price_order()
+                || (self.entry_window == 0 && self.mode == MarketMode::FixedOdds),
+            OptnError::UnorderedPriceSource
+        );
+
+        Ok(())
+    }
+
+    pub fn load_price(&self, price_update: &AccountInfo) -> Result<OraclePrice> {
+        load_price(
+            self.price_source,
+            &self.feed_id,
+            price_update,
+            &self.switchboard_program_id,
+        )
+    }
+
+    /// Whether the feed trades at the timestamp, sessions belong to the weekday they open on
+    pub fn is_open(&self, timestamp: i64) -> bool {
+        if self
+            .blackout_windows
+            .iter()
+            .any(|window| window.start <= timestamp && timestamp < window.end)
+        {
+            return false;
+        }
+
+        let mut day = timestamp.div_euclid(SECONDS_PER_DAY);
+        let time_of_day = timestamp.rem_euclid(SECONDS_PER_DAY) as u32;
+        if self.open_time < self.close_time {
+            if time_of_day < self.open_time || time_of_day >= self.close_time {
+                return false;
+            }
+        } else if self.open_time > self.close_time {
+            if time_of_day < self.close_time {
+                day -= 1;
+            } else if time_of_day < self.open_time {
+                return false;
+            }
+        }
+
+        // the unix epoch fell on a Thursday
+        let weekday = (day + 3).rem_euclid(7);
+        self.trading_days & (1 << weekday) != 0
+    }
+
+    /// Scale a payout down when EMA confidence is wider than the reference
+    pub fn volatility_adjusted(&self, amount: u64, price: &OraclePrice) -> u64 {
+        if self.reference_conf_basis_points == 0 || price.ema_price <= 0 {
+            return amount;
+        }
+
+        let conf_basis_points =
+            price.ema_conf as u128 * BASIS_POINTS_DIVISOR / price.ema_price as u128;
+        if conf_basis_points <= self.reference_conf_basis_points as u128 {
+            return amount;
+        }
+
+        u64::try_from(amount as u128 * self.reference_conf_basis_points as u128 / conf_basis_points)
+            .unwrap()
+    }
+
+    /// Reject prices the secondary oracle disagrees with, if one is configured
+    pub fn cross_check(
+        &self,
+        price: &OraclePrice,
+        secondary_price_update: Option<&AccountInfo>,
+    ) -> Result<()> {
+        let Some(secondary_key) = self.secondary_price_update else {
+            return Ok(());
+        };
+        let secondary_price_update =
+            secondary_price_update.ok_or(error!(OptnError::InvalidPriceAccount))?;
+        require_keys_eq!(
+            secondary_price_update.key(),
+            secondary_key,
+            OptnError::InvalidPriceAccount
+        );
+
+        let secondary_price = load_price(
+            self.secondary_price_source,
+            &self.secondary_feed_id,
+            secondary_price_update,
+            &self.switchboard_program_id,
+        )?;
+        require_gte!(
+            self.max_price_age as i64,
+            (price.publish_time - secondary_price.publish_time).abs(),
+            OptnError::StalePrice
+        );
+        require_gte!(
+            self.max_deviation_basis_points as u128,
+            price.deviation_basis_points(&secondary_price),
+            OptnError::OracleDeviation
+        );
+
+        Ok(())
+    }
+
+    /// Reject a historical price the secondary oracle disagrees with, if one is configured,
+    /// comparing it with the first secondary update at or after the same timestamp
+    pub fn cross_check_at(
+        &self,
+        price: &OraclePrice,
+        secondary_price_update: Option<&AccountInfo>,
+        timestamp: i64,
+    ) -> Result<()> {
+        if self.secondary_price_update.is_none() {
+            return Ok(());
+        }
+        let secondary_price_update =
+            secondary_price_update.ok_or(error!(OptnError::InvalidPriceAccount))?;
+
+        let secondary_price = load_price(
+            self.secondary_price_source,
+            &self.secondary_feed_id,
+            secondary_price_update,
+            &self.switchboard_program_id,
+        )?;
+        require!(
+            secondary_price.is_first_at_or_after(timestamp),
+            OptnError::NotFirstPrice
+        );
+        require_gte!(
+            self.max_deviation_basis_points as u128,
+            price.deviation_basis_points(&secondary_price),
+            OptnError::OracleDeviation
+        );
+
+        Ok(())
+    }
 }
 
 #[account]
@@ -545,8 +3262,22 @@ pub struct Bet {
     pub final_payout: u64,
 
     pub entry_price: u64,
+    /// Strike for binary bets, barrier for touch bets
+    pub strike_price: u64,
+    /// Band bounds for range bets
+    pub lower_price: u64,
+    pub upper_price: u64,
+    /// Extremes of the recorded price observations
+    pub high_price: u64,
+    pub low_price: u64,
     pub settled_price: u64,
+
+    /// Offsets applied to the entry price once it is fixed
+    pub strike_offset_basis_points: i16,
+    pub range_basis_points: u16,
+
     pub direction: Direction,
+    pub kind: BetKind,
     pub status: Status,
 
     pub created_at: i64,
@@ -554,6 +3285,251 @@ pub struct Bet {
     pub settled_at: Option<i64>,
 }
 
+impl Bet {
+    pub fn set_entry_price(&mut self, entry_price: u64) {
+        self.entry_price = entry_price;
+        self.strike_price = offset_price(entry_price, self.strike_offset_basis_points as i32);
+        if self.range_basis_points > 0 {
+            self.lower_price = offset_price(entry_price, -(self.range_basis_points as i32));
+            self.upper_price = offset_price(entry_price, self.range_basis_points as i32);
+        }
+        self.high_price = entry_price;
+        self.low_price = entry_price;
+    }
+
+    pub fn record_price(&mut self, price: u64) {
+        self.high_price = self.high_price.max(price);
+        self.low_price = self.low_price.min(price);
+    }
+
+    pub fn is_touched(&self) -> bool {
+        match self.direction {
+            Direction::Buy => self.high_price >= self.strike_price,
+            Direction::Sell => self.strike_price >= self.low_price,
+        }
+    }
+
+    pub fn is_won(&self, settled_price: u64) -> bool {
+        match self.kind {
+            BetKind::Binary => match self.direction {
+                Direction::Buy => settled_price > self.strike_price,
+                Direction::Sell => self.strike_price > settled_price,
+            },
+            BetKind::Inside => {
+                settled_price >= self.lower_price && self.upper_price >= settled_price
+            }
+            BetKind::Outside => {
+                self.lower_price > settled_price || settled_price > self.upper_price
+            }
+            BetKind::Touch => self.is_touched(),
+            BetKind::NoTouch => !self.is_touched(),
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SettlementPrice {
+    pub market: Pubkey,
+    pub timestamp: i64,
+
+    pub price: u64,
+    /// Publish time of the price update, 0 when provided by the admin
+    pub publish_time: i64,
+    pub source: SettlementSource,
+    /// Admin prices are provisional until this time, 0 when final
+    pub disputable_until: i64,
+
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LiquidityPosition {
+    pub house: Pubkey,
+    pub authority: Pubkey,
+
+    pub shares: u64,
+    pub locked_until: i64,
+
+    /// Shares queued for redemption, claimable once their epoch ends
+    pub withdrawal_shares: u64,
+    pub withdrawal_epoch: u64,
+
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Epoch {
+    pub house: Pubkey,
+    pub epoch: u64,
+
+    /// Lamports per share at the end of the epoch
+    /// Precision: 9 decimal places
+    pub share_price: u64,
+    /// Redeemed shares and lamports not yet claimed
+    pub withdrawal_shares: u64,
+    pub withdrawal_amount: u64,
+
+    pub started_at: i64,
+    pub ended_at: i64,
+
+    /// Ended by close_house, every share left in a position is redeemed in it
+    pub final_epoch: bool,
+
+    pub bump: u8,
+}
+
+impl Epoch {
+    /// Lamports owed for redeemed shares, the last claim takes the rounding dust so the epoch
+    /// is fully paid out
+    pub fn payout(&self, shares: u64) -> u64 {
+        if shares == self.withdrawal_shares {
+            self.withdrawal_amount
+        } else {
+            u64::try_from(shares as u128 * self.share_price as u128 / MULTIPLIER_PRECISION).unwrap()
+        }
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalRequest {
+    pub house: Pubkey,
+    pub amount: u64,
+    pub status: WithdrawalStatus,
+
+    pub requested_at: i64,
+    pub executable_at: i64,
+
+    pub bump: u8,
+}
+
+#[account]
+pub struct Round {
+    pub market: Pubkey,
+
+    pub buy_pool: u64,
+    pub sell_pool: u64,
+    pub fee_amount: u64,
+
+    pub lock_price: u64,
+    pub close_price: u64,
+    pub status: RoundStatus,
+
+    /// Positions not yet claimed
+    pub active_positions: u32,
+    /// Payouts owed to the positions not yet claimed
+    pub unclaimed_payouts: u64,
+
+    pub lock_time: i64,
+    pub close_time: i64,
+}
+
+impl Round {
+    /// Pool on the winning side, 0 when the round is a tie
+    pub fn winning_pool(&self) -> u64 {
+        if self.close_price > self.lock_price {
+            self.buy_pool
+        } else if self.lock_price > self.close_price {
+            self.sell_pool
+        } else {
+            0
+        }
+    }
+
+    pub fn payout(&self, position: &Position) -> u64 {
+        match self.status {
+            RoundStatus::Canceled => position.amount,
+            RoundStatus::Ended => {
+                let won = match position.direction {
+                    Direction::Buy => self.close_price > self.lock_price,
+                    Direction::Sell => self.lock_price > self.close_price,
+                };
+                if !won {
+                    return 0;
+                }
+
+                // winners share the pool after the house fee pro rata
+                let prize_pool = self.buy_pool + self.sell_pool - self.fee_amount;
+                u64::try_from(
+                    position.amount as u128 * prize_pool as u128 / self.winning_pool() as u128,
+                )
+                .unwrap()
+            }
+            _ => 0,
+        }
+    }
+}
+
+#[account]
+pub struct Position {
+    pub round: Pubkey,
+    pub authority: Pubkey,
+
+    pub direction: Direction,
+    pub amount: u64,
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarketMode {
+    FixedOdds = 0,  // Bets against house liquidity
+    Parimutuel = 1, // Rounds where winners share the losing pool
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum SettlementSource {
+    Oracle = 0, // Recorded from a verified price update
+    Admin = 1,  // Provided by the admin as a fallback
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum WithdrawalStatus {
+    Pending = 0,  // Waiting out the withdrawal delay
+    Executed = 1, // Paid out to the beneficiary
+    Canceled = 2, // Withdrawn by the beneficiary before execution
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarketStatus {
+    Active = 0,     // Accepting new bets and rounds
+    ReduceOnly = 1, // Settling open bets and rounds only
+    Closed = 2,     // Retired, closable once nothing is left to settle
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriceSource {
+    Pyth = 0,        // Pyth receiver PriceUpdateV2 account
+    Switchboard = 1, // Switchboard On-Demand pull feed account, settled by admin price only
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct RevenueShare {
+    pub recipient: Pubkey,
+    pub basis_points: u16,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default)]
+pub struct BlackoutWindow {
+    pub start: i64,
+    pub end: i64,
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundStatus {
+    Open = 0,     // Accepting wagers until lock time
+    Locked = 1,   // Lock price recorded
+    Ended = 2,    // Close price recorded, winners can claim
+    Canceled = 3, // Tie, one-sided or never locked, everyone is refunded
+}
+
 #[repr(u8)]
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Direction {
@@ -561,13 +3537,25 @@ pub enum Direction {
     Sell = 1, // Short (Put)
 }
 
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BetKind {
+    Binary = 0,  // Settles against the strike in the bet direction
+    Inside = 1,  // Settles inside the band, direction is ignored
+    Outside = 2, // Settles outside the band, direction is ignored
+    Touch = 3,   // Settles if the barrier was reached before expiry
+    NoTouch = 4, // Settles if the barrier was never reached before expiry
+}
+
 #[repr(u8)]
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Status {
     Pending = 0,
     Won = 1,
     Lose = 2,
-    Canceled = 3, // Settlement failed due to missing price
+    Canceled = 3,  // Canceled by the bettor or settlement failed due to missing price
+    CashedOut = 4, // Sold back to the house before expiry
+    AwaitingEntry = 5, // Delayed entry, waiting for activation
 }
 
 #[error_code]
@@ -590,14 +3578,167 @@ pub enum OptnError {
     #[msg("Bet cannot be settled because it is still active")]
     BetNotExpired,
 
-    #[msg("Bet cannot be settled again because it has already been resolved")]
-    BetSettled,
+    #[msg("Bet cannot be settled again because it has already been resolved")]
+    BetSettled,
+
+    #[msg("Bet cannot be closed because its outcome is still pending")]
+    BetPending,
+
+    #[msg("No claimable profit available for the house")]
+    NoProfit,
+
+    #[msg("Bet cancellation is disabled for this house")]
+    CancelDisabled,
+
+    #[msg("Bet can no longer be canceled because the cancel window has passed")]
+    CancelWindowClosed,
+
+    #[msg("Cash-out is disabled for this market")]
+    CashOutDisabled,
+
+    #[msg("Bet can no longer be cashed out because it has expired")]
+    BetExpired,
+
+    #[msg("Strike offset exceeds the allowed maximum limit")]
+    MaxStrikeOffset,
+
+    #[msg("Bet kind is disabled for this house")]
+    BetKindDisabled,
+
+    #[msg("Price observations can only be recorded on touch bets")]
+    NotTouchBet,
+
+    #[msg("Price update is not fully verified")]
+    UnverifiedPrice,
+
+    #[msg("Price was not published between the bet creation and expiry")]
+    PriceOutsideBetWindow,
+
+    #[msg("Instruction is not supported in this market mode")]
+    MarketModeMismatch,
+
+    #[msg("Round no longer accepts wagers")]
+    RoundLocked,
+
+    #[msg("Round cannot be updated before its scheduled time")]
+    RoundNotReady,
+
+    #[msg("Round has already been resolved")]
+    RoundSettled,
+
+    #[msg("Position cannot be claimed because the round is still pending")]
+    RoundPending,
+
+    #[msg("Price was not published within the round window")]
+    PriceOutsideRoundWindow,
+
+    #[msg("Settlement price is provisional until its dispute window has passed")]
+    SettlementPriceProvisional,
+
+    #[msg("Settlement price can no longer be disputed")]
+    DisputeWindowClosed,
+
+    #[msg("Price update matches the recorded settlement price")]
+    SettlementPriceMatches,
+
+    #[msg("Refunds of unsettled bets are disabled for this house")]
+    RefundDisabled,
+
+    #[msg("Bet cannot be refunded before the settlement deadline")]
+    SettlementDeadlineNotReached,
+
+    #[msg("Bet has already been activated")]
+    BetActivated,
+
+    #[msg("Price was not published within the bet entry window")]
+    PriceOutsideEntryWindow,
+
+    #[msg("Bet cannot be refunded while its entry window is open")]
+    EntryWindowOpen,
+
+    #[msg("Market has active bets, rounds or unclaimed payouts")]
+    MarketHasActiveBets,
+
+    #[msg("Price update does not match the market feed precision")]
+    OracleMismatch,
+
+    #[msg("Price account is not owned by the market oracle or does not carry its feed")]
+    InvalidPriceAccount,
+
+    #[msg("Price is older than the allowed maximum age")]
+    StalePrice,
+
+    #[msg("Primary and secondary oracle prices deviate beyond the allowed limit")]
+    OracleDeviation,
+
+    #[msg("Market is closed at the bet expiry")]
+    MarketClosed,
+
+    #[msg("Market is not accepting new bets")]
+    MarketInactive,
+
+    #[msg("Market must be closed first")]
+    MarketNotClosed,
+
+    #[msg("House has active markets")]
+    HouseHasActiveMarkets,
+
+    #[msg("Transfer would leave the house authority below its rent-exempt minimum")]
+    RentFloor,
+
+    #[msg("House authority balance does not cover the house obligations")]
+    Insolvent,
+
+    #[msg("Withdrawal must wait out the house withdrawal delay")]
+    WithdrawalDelayed,
+
+    #[msg("Liquidity belongs to liquidity providers, redeem their shares first")]
+    LiquidityShared,
+
+    #[msg("Shares have no liquidity behind them, redeem them first")]
+    LiquidityExhausted,
+
+    #[msg("Deposited liquidity is still locked")]
+    LiquidityLocked,
+
+    #[msg("A liquidity withdrawal is already queued")]
+    LiquidityWithdrawalQueued,
+
+    #[msg("Not enough shares")]
+    InsufficientShares,
+
+    #[msg("Epoch has not run its duration")]
+    EpochNotEnded,
+
+    #[msg("No liquidity withdrawal to claim")]
+    NoLiquidityWithdrawal,
+
+    #[msg("Revenue split weights must be positive and add up to 100%")]
+    InvalidRevenueSplit,
+
+    #[msg("Remaining accounts do not match the revenue split recipients")]
+    RevenueRecipientMismatch,
+
+    #[msg("Price update is not the first published at or after the timestamp")]
+    NotFirstPrice,
+
+    #[msg("House has active bets")]
+    HouseHasActiveBets,
+
+    #[msg("House has unclaimed payouts")]
+    HouseHasUnclaimedPayouts,
+
+    #[msg("House profit must be claimed first")]
+    UnclaimedProfit,
 
-    #[msg("Bet cannot be closed because its outcome is still pending")]
-    BetPending,
+    #[msg("Price source cannot prove the first update at or after a timestamp")]
+    UnorderedPriceSource,
 
-    #[msg("No claimable profit available for the house")]
-    NoProfit,
+    #[msg("House is still open")]
+    HouseNotClosed,
+
+    #[msg("Price must be positive")]
+    NonPositivePrice,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
@@ -608,6 +3749,12 @@ pub struct HouseUpdatedData {
     pub active_bets: u32,
     pub settled_bets: u32,
     pub canceled_bets: u32,
+    pub active_markets: u32,
+    pub unclaimed_payouts: u64,
+    pub total_shares: u64,
+    pub beneficiary_shares: u64,
+    pub share_price: u64,
+    pub epoch: u64,
 }
 
 #[event]
@@ -626,17 +3773,80 @@ pub fn emit_house_updated(pubkey: &Pubkey, house: &House) {
             active_bets: house.active_bets,
             settled_bets: house.settled_bets,
             canceled_bets: house.canceled_bets,
+            active_markets: house.active_markets,
+            unclaimed_payouts: house.unclaimed_payouts,
+            total_shares: house.total_shares,
+            beneficiary_shares: house.beneficiary_shares,
+            share_price: house.share_price,
+            epoch: house.epoch,
         },
     });
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct HouseClosedData {
+    pub beneficiary: Pubkey,
+    /// Lamports swept from the house authority
+    pub amount: u64,
+    pub liquidity: u64,
+    pub total_profit: u64,
+    pub total_deposits: u64,
+    pub total_withdrawals: u64,
+    pub total_wagered: u64,
+    pub claimed_profits: u64,
+    pub settled_bets: u32,
+    pub canceled_bets: u32,
+}
+
+#[event]
+pub struct HouseClosedEvent {
+    pub pubkey: Pubkey,
+    pub data: HouseClosedData,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ProfitDistributedData {
+    pub total_profit: u64,
+    /// Revenue split recipients in table order, then the beneficiary for any remainder
+    pub recipients: Vec<Pubkey>,
+    pub amounts: Vec<u64>,
+}
+
+#[event]
+pub struct ProfitDistributedEvent {
+    pub pubkey: Pubkey,
+    pub data: ProfitDistributedData,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SolvencyReportData {
+    /// Lamports held by the house authority
+    pub balance: u64,
+    pub liquidity: u64,
+    pub total_wagered: u64,
+    pub total_profit: u64,
+    pub unclaimed_payouts: u64,
+    pub rent_floor: u64,
+    /// Sum of everything above the balance must cover
+    pub obligations: u64,
+}
+
+#[event]
+pub struct SolvencyReportEvent {
+    pub pubkey: Pubkey,
+    pub data: SolvencyReportData,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct MarketUpdatedData {
+    pub status: MarketStatus,
     pub reserved_liquidity: u64,
     pub total_wagered: u64,
+    pub unclaimed_payouts: u64,
     pub active_bets: u32,
     pub settled_bets: u32,
     pub canceled_bets: u32,
+    pub open_bets: u32,
 }
 
 #[event]
@@ -649,11 +3859,50 @@ pub fn emit_market_updated(pubkey: &Pubkey, market: &Market) {
     emit!(MarketUpdatedEvent {
         pubkey: pubkey.key(),
         data: MarketUpdatedData {
+            status: market.status,
             reserved_liquidity: market.reserved_liquidity,
             total_wagered: market.total_wagered,
+            unclaimed_payouts: market.unclaimed_payouts,
             active_bets: market.active_bets,
             settled_bets: market.settled_bets,
             canceled_bets: market.canceled_bets,
+            open_bets: market.open_bets,
+        },
+    });
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct MarketOracleUpdatedData {
+    pub price_source: PriceSource,
+    pub price_update: Pubkey,
+    pub feed_id: [u8; 32],
+    pub decimals: u8,
+    pub switchboard_program_id: Pubkey,
+    pub secondary_price_source: PriceSource,
+    pub secondary_price_update: Option<Pubkey>,
+    pub secondary_feed_id: [u8; 32],
+    pub max_deviation_basis_points: u16,
+}
+
+#[event]
+pub struct MarketOracleUpdatedEvent {
+    pub pubkey: Pubkey,
+    pub data: MarketOracleUpdatedData,
+}
+
+pub fn emit_market_oracle_updated(pubkey: &Pubkey, market: &Market) {
+    emit!(MarketOracleUpdatedEvent {
+        pubkey: pubkey.key(),
+        data: MarketOracleUpdatedData {
+            price_source: market.price_source,
+            price_update: market.price_update,
+            feed_id: market.feed_id,
+            decimals: market.decimals,
+            switchboard_program_id: market.switchboard_program_id,
+            secondary_price_source: market.secondary_price_source,
+            secondary_price_update: market.secondary_price_update,
+            secondary_feed_id: market.secondary_feed_id,
+            max_deviation_basis_points: market.max_deviation_basis_points,
         },
     });
 }
@@ -666,8 +3915,14 @@ pub struct BetCreatedData {
     pub profit_amount: u64,
     pub final_payout: u64,
     pub entry_price: u64,
+    pub strike_price: u64,
+    pub lower_price: u64,
+    pub upper_price: u64,
+    pub high_price: u64,
+    pub low_price: u64,
     pub settled_price: u64,
     pub direction: Direction,
+    pub kind: BetKind,
     pub status: Status,
     pub created_at: i64,
     pub expires_at: i64,
@@ -690,8 +3945,14 @@ pub fn emit_bet_created(pubkey: &Pubkey, bet: &Bet) {
             profit_amount: bet.profit_amount,
             final_payout: bet.final_payout,
             entry_price: bet.entry_price,
+            strike_price: bet.strike_price,
+            lower_price: bet.lower_price,
+            upper_price: bet.upper_price,
+            high_price: bet.high_price,
+            low_price: bet.low_price,
             settled_price: bet.settled_price,
             direction: bet.direction.clone(),
+            kind: bet.kind.clone(),
             status: bet.status.clone(),
             created_at: bet.created_at,
             expires_at: bet.expires_at,
@@ -702,7 +3963,14 @@ pub fn emit_bet_created(pubkey: &Pubkey, bet: &Bet) {
 
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct BetUpdatedData {
+    pub profit_amount: u64,
     pub final_payout: u64,
+    pub entry_price: u64,
+    pub strike_price: u64,
+    pub lower_price: u64,
+    pub upper_price: u64,
+    pub high_price: u64,
+    pub low_price: u64,
     pub settled_price: u64,
     pub status: Status,
     pub settled_at: Option<i64>,
@@ -718,10 +3986,726 @@ pub fn emit_bet_updated(pubkey: &Pubkey, bet: &Bet) {
     emit!(BetUpdatedEvent {
         pubkey: pubkey.key(),
         data: BetUpdatedData {
+            profit_amount: bet.profit_amount,
             final_payout: bet.final_payout,
+            entry_price: bet.entry_price,
+            strike_price: bet.strike_price,
+            lower_price: bet.lower_price,
+            upper_price: bet.upper_price,
+            high_price: bet.high_price,
+            low_price: bet.low_price,
             settled_price: bet.settled_price,
             status: bet.status.clone(),
             settled_at: bet.settled_at.clone(),
         },
     });
 }
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct RoundUpdatedData {
+    pub buy_pool: u64,
+    pub sell_pool: u64,
+    pub fee_amount: u64,
+    pub lock_price: u64,
+    pub close_price: u64,
+    pub status: RoundStatus,
+    pub active_positions: u32,
+    pub lock_time: i64,
+    pub close_time: i64,
+}
+
+#[event]
+pub struct RoundUpdatedEvent {
+    pub pubkey: Pubkey,
+    pub data: RoundUpdatedData,
+}
+
+pub fn emit_round_updated(pubkey: &Pubkey, round: &Round) {
+    emit!(RoundUpdatedEvent {
+        pubkey: pubkey.key(),
+        data: RoundUpdatedData {
+            buy_pool: round.buy_pool,
+            sell_pool: round.sell_pool,
+            fee_amount: round.fee_amount,
+            lock_price: round.lock_price,
+            close_price: round.close_price,
+            status: round.status,
+            active_positions: round.active_positions,
+            lock_time: round.lock_time,
+            close_time: round.close_time,
+        },
+    });
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct SettlementPriceUpdatedData {
+    pub market: Pubkey,
+    pub timestamp: i64,
+    pub price: u64,
+    pub publish_time: i64,
+    pub source: SettlementSource,
+    pub disputable_until: i64,
+}
+
+#[event]
+pub struct SettlementPriceUpdatedEvent {
+    pub pubkey: Pubkey,
+    pub data: SettlementPriceUpdatedData,
+}
+
+pub fn emit_settlement_price_updated(pubkey: &Pubkey, settlement_price: &SettlementPrice) {
+    emit!(SettlementPriceUpdatedEvent {
+        pubkey: pubkey.key(),
+        data: SettlementPriceUpdatedData {
+            market: settlement_price.market,
+            timestamp: settlement_price.timestamp,
+            price: settlement_price.price,
+            publish_time: settlement_price.publish_time,
+            source: settlement_price.source,
+            disputable_until: settlement_price.disputable_until,
+        },
+    });
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct WithdrawalRequestUpdatedData {
+    pub house: Pubkey,
+    pub amount: u64,
+    pub status: WithdrawalStatus,
+    pub requested_at: i64,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct WithdrawalRequestUpdatedEvent {
+    pub pubkey: Pubkey,
+    pub data: WithdrawalRequestUpdatedData,
+}
+
+pub fn emit_withdrawal_request_updated(pubkey: &Pubkey, withdrawal_request: &WithdrawalRequest) {
+    emit!(WithdrawalRequestUpdatedEvent {
+        pubkey: pubkey.key(),
+        data: WithdrawalRequestUpdatedData {
+            house: withdrawal_request.house,
+            amount: withdrawal_request.amount,
+            status: withdrawal_request.status,
+            requested_at: withdrawal_request.requested_at,
+            executable_at: withdrawal_request.executable_at,
+        },
+    });
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct LiquidityPositionUpdatedData {
+    pub house: Pubkey,
+    pub authority: Pubkey,
+    pub shares: u64,
+    pub locked_until: i64,
+    pub withdrawal_shares: u64,
+    pub withdrawal_epoch: u64,
+}
+
+#[event]
+pub struct LiquidityPositionUpdatedEvent {
+    pub pubkey: Pubkey,
+    pub data: LiquidityPositionUpdatedData,
+}
+
+pub fn emit_liquidity_position_updated(pubkey: &Pubkey, liquidity_position: &LiquidityPosition) {
+    emit!(LiquidityPositionUpdatedEvent {
+        pubkey: pubkey.key(),
+        data: LiquidityPositionUpdatedData {
+            house: liquidity_position.house,
+            authority: liquidity_position.authority,
+            shares: liquidity_position.shares,
+            locked_until: liquidity_position.locked_until,
+            withdrawal_shares: liquidity_position.withdrawal_shares,
+            withdrawal_epoch: liquidity_position.withdrawal_epoch,
+        },
+    });
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct EpochUpdatedData {
+    pub house: Pubkey,
+    pub epoch: u64,
+    pub share_price: u64,
+    pub withdrawal_shares: u64,
+    pub withdrawal_amount: u64,
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub final_epoch: bool,
+}
+
+#[event]
+pub struct EpochUpdatedEvent {
+    pub pubkey: Pubkey,
+    pub data: EpochUpdatedData,
+}
+
+pub fn emit_epoch_updated(pubkey: &Pubkey, epoch: &Epoch) {
+    emit!(EpochUpdatedEvent {
+        pubkey: pubkey.key(),
+        data: EpochUpdatedData {
+            house: epoch.house,
+            epoch: epoch.epoch,
+            share_price: epoch.share_price,
+            withdrawal_shares: epoch.withdrawal_shares,
+            withdrawal_amount: epoch.withdrawal_amount,
+            started_at: epoch.started_at,
+            ended_at: epoch.ended_at,
+            final_epoch: epoch.final_epoch,
+        },
+    });
+}
+
+/// Oracle reading normalized across price sources
+#[derive(Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    /// Publish time of the update before this one, None when the source does not record it
+    pub prev_publish_time: Option<i64>,
+}
+
+impl OraclePrice {
+    /// Whether this is the first update published at or after the timestamp
+    pub fn is_first_at_or_after(&self, timestamp: i64) -> bool {
+        match self.prev_publish_time {
+            Some(prev_publish_time) => {
+                prev_publish_time < timestamp && timestamp <= self.publish_time
+            }
+            None => false,
+        }
+    }
+
+    pub fn no_older_than(self, clock: &Clock, max_price_age: u64) -> Result<Self> {
+        require_gte!(
+            self.publish_time + max_price_age as i64,
+            clock.unix_timestamp,
+            OptnError::StalePrice
+        );
+        Ok(self)
+    }
+
+    /// Relative difference to another reading after aligning exponents
+    pub fn deviation_basis_points(&self, other: &OraclePrice) -> u128 {
+        let exponent = self.exponent.min(other.exponent);
+        let price = self.price as i128 * 10i128.pow((self.exponent - exponent) as u32);
+        let other_price = other.price as i128 * 10i128.pow((other.exponent - exponent) as u32);
+
+        (price - other_price).unsigned_abs() * BASIS_POINTS_DIVISOR / price.unsigned_abs().max(1)
+    }
+}
+
+trait PriceFeed {
+    /// Latest verified reading for the feed, without a staleness check
+    fn read_price(&self, feed_id: &[u8; 32]) -> Result<OraclePrice>;
+}
+
+impl PriceFeed for PriceUpdateV2 {
+    fn read_price(&self, feed_id: &[u8; 32]) -> Result<OraclePrice> {
+        require!(
+            self.verification_level == VerificationLevel::Full,
+            OptnError::UnverifiedPrice
+        );
+        let price = self.get_price_unchecked(feed_id)?;
+
+        Ok(OraclePrice {
+            price: price.price,
+            conf: price.conf,
+            ema_price: self.price_message.ema_price,
+            ema_conf: self.price_message.ema_conf,
+            exponent: price.exponent,
+            publish_time: price.publish_time,
+            prev_publish_time: Some(self.price_message.prev_publish_time),
+        })
+    }
+}
+
+impl PriceFeed for PullFeedAccountData {
+    fn read_price(&self, feed_id: &[u8; 32]) -> Result<OraclePrice> {
+        require!(self.feed_hash == *feed_id, OptnError::InvalidPriceAccount);
+        let scale = 10i128.pow(switchboard_on_demand::PRECISION - SWITCHBOARD_DECIMALS);
+
+        // median of the fresh submissions, rejected when the feed lacks its minimum samples
+        let mut value = self
+            .get_value(
+                &Clock::get()?,
+                self.max_staleness as u64,
+                self.min_sample_size as u32,
+                true,
+            )
+            .map_err(|_| error!(OptnError::InvalidPriceAccount))?;
+        value.rescale(SWITCHBOARD_DECIMALS);
+
+        Ok(OraclePrice {
+            price: i64::try_from(value.mantissa()).unwrap(),
+            conf: u64::try_from(self.result.std_dev / scale).unwrap(),
+            // no EMA on pull feeds, the mean and spread of the submissions stand in
+            ema_price: i64::try_from(self.result.mean / scale).unwrap(),
+            ema_conf: u64::try_from(self.result.std_dev / scale).unwrap(),
+            exponent: -(SWITCHBOARD_DECIMALS as i32),
+            publish_time: self.last_update_timestamp,
+            // pull feeds keep no update history, so their readings cannot prove ordering
+            prev_publish_time: None,
+        })
+    }
+}
+
+/// Read a feed from an oracle account after checking it belongs to the price source
+pub fn load_price(
+    price_source: PriceSource,
+    feed_id: &[u8; 32],
+    price_update: &AccountInfo,
+    switchboard_program_id: &Pubkey,
+) -> Result<OraclePrice> {
+    match price_source {
+        PriceSource::Pyth => {
+            require_keys_eq!(
+                *price_update.owner,
+                pyth_solana_receiver_sdk::ID,
+                OptnError::InvalidPriceAccount
+            );
+            PriceUpdateV2::try_deserialize(&mut &price_update.try_borrow_data()?[..])?
+                .read_price(feed_id)
+        }
+        PriceSource::Switchboard => {
+            require_keys_eq!(
+                *price_update.owner,
+                *switchboard_program_id,
+                OptnError::InvalidPriceAccount
+            );
+            PullFeedAccountData::parse(price_update.try_borrow_data()?)
+                .map_err(|_| error!(OptnError::InvalidPriceAccount))?
+                .read_price(feed_id)
+        }
+    }
+}
+
+/// Reject transfers that would leave the house authority below its rent-exempt minimum
+fn validate_rent_floor(house_authority: &AccountInfo, amount: u64) -> Result<()> {
+    require_gte!(
+        house_authority.lamports().saturating_sub(amount),
+        Rent::get()?.minimum_balance(0),
+        OptnError::RentFloor
+    );
+
+    Ok(())
+}
+
+/// Square root of an integer, rounded down
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+
+    let mut x = value;
+    let mut y = x.div_ceil(2);
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+/// Natural log of numerator / denominator, fixed-point at PROBABILITY_PRECISION, both must be
+/// positive
+fn ln_ratio(mut numerator: u128, mut denominator: u128) -> i128 {
+    // reduce the ratio into [1, 2) by powers of two
+    let mut exponent = 0i128;
+    if numerator >= denominator {
+        while numerator >= denominator << 1 {
+            denominator <<= 1;
+            exponent += 1;
+        }
+    } else {
+        while numerator < denominator {
+            numerator <<= 1;
+            exponent -= 1;
+        }
+    }
+
+    // ln(y) = 2 atanh((y - 1) / (y + 1)), the series converges fast for y in [1, 2)
+    let y = (numerator * PROBABILITY_PRECISION as u128 / denominator) as i128;
+    let z = (y - PROBABILITY_PRECISION) * PROBABILITY_PRECISION / (y + PROBABILITY_PRECISION);
+    let z_squared = z * z / PROBABILITY_PRECISION;
+
+    let mut series = 0;
+    let mut term = z;
+    let mut index = 1;
+    while term != 0 {
+        series += term / index;
+        term = term * z_squared / PROBABILITY_PRECISION;
+        index += 2;
+    }
+
+    2 * series + exponent * LN_2
+}
+
+/// e^-value for a non-negative fixed-point value
+fn exp_neg(value: i128) -> i128 {
+    // e^-value = 2^-n * e^-r with r in [0, ln 2)
+    let halvings = value / LN_2;
+    if halvings >= 64 {
+        return 0;
+    }
+    let remainder = value - halvings * LN_2;
+
+    let mut result = PROBABILITY_PRECISION;
+    let mut term = PROBABILITY_PRECISION;
+    let mut index = 1;
+    while term != 0 {
+        term = -term * remainder / PROBABILITY_PRECISION / index;
+        result += term;
+        index += 1;
+    }
+
+    result >> halvings
+}
+
+/// Standard normal cumulative distribution (Abramowitz & Stegun 26.2.17)
+fn normal_cdf(x: i128) -> i128 {
+    // the tails are flat to well below the precision beyond 10 standard deviations
+    let x = x.clamp(-10 * PROBABILITY_PRECISION, 10 * PROBABILITY_PRECISION);
+
+    let t = PROBABILITY_PRECISION * PROBABILITY_PRECISION
+        / (PROBABILITY_PRECISION + 231_641_900 * x.abs() / PROBABILITY_PRECISION);
+    let density = exp_neg(x * x / PROBABILITY_PRECISION / 2) * 398_942_280 / PROBABILITY_PRECISION;
+
+    let mut polynomial = 1_330_274_429;
+    for coefficient in [-1_821_255_978, 1_781_477_937, -356_563_782, 319_381_530] {
+        polynomial = coefficient + t * polynomial / PROBABILITY_PRECISION;
+    }
+    let tail = (density * t / PROBABILITY_PRECISION * polynomial / PROBABILITY_PRECISION)
+        .clamp(0, PROBABILITY_PRECISION);
+
+    if x >= 0 {
+        PROBABILITY_PRECISION - tail
+    } else {
+        tail
+    }
+}
+
+/// Probability the price finishes above the strike (zero rates)
+fn probability_above(current_price: u64, strike_price: u64, sigma_sqrt_t: i128) -> i128 {
+    let log_moneyness = ln_ratio(current_price as u128, strike_price as u128);
+
+    normal_cdf(
+        (log_moneyness - sigma_sqrt_t * sigma_sqrt_t / PROBABILITY_PRECISION / 2)
+            * PROBABILITY_PRECISION
+            / sigma_sqrt_t,
+    )
+}
+
+/// Fair value of a pending bet (zero rates) less the house spread
+pub fn cash_out_value(
+    bet: &Bet,
+    current_price: u64,
+    volatility_basis_points: u32,
+    spread_basis_points: u16,
+    time_remaining: i64,
+) -> Result<u64> {
+    // validate prices, the log of a zero price is undefined
+    require!(
+        current_price > 0
+            && bet.strike_price > 0
+            && (bet.range_basis_points == 0 || bet.lower_price > 0),
+        OptnError::NonPositivePrice
+    );
+
+    let sqrt_t = isqrt(
+        time_remaining as u128 * (PROBABILITY_PRECISION * PROBABILITY_PRECISION) as u128
+            / SECONDS_PER_YEAR as u128,
+    ) as i128;
+    let sigma_sqrt_t =
+        (volatility_basis_points as i128 * sqrt_t / BASIS_POINTS_DIVISOR as i128).max(1);
+
+    let win_probability = match bet.kind {
+        BetKind::Binary => {
+            let above = probability_above(current_price, bet.strike_price, sigma_sqrt_t);
+            match bet.direction {
+                Direction::Buy => above,
+                Direction::Sell => PROBABILITY_PRECISION - above,
+            }
+        }
+        BetKind::Inside | BetKind::Outside => {
+            let inside = probability_above(current_price, bet.lower_price, sigma_sqrt_t)
+                - probability_above(current_price, bet.upper_price, sigma_sqrt_t);
+            match bet.kind {
+                BetKind::Inside => inside,
+                _ => PROBABILITY_PRECISION - inside,
+            }
+        }
+        BetKind::Touch | BetKind::NoTouch => {
+            // reflection principle: a driftless path touches the barrier with twice the
+            // probability of finishing beyond it
+            let touch = if bet.is_touched() {
+                PROBABILITY_PRECISION
+            } else {
+                (2 * normal_cdf(
+                    -ln_ratio(bet.strike_price as u128, current_price as u128).abs()
+                        * PROBABILITY_PRECISION
+                        / sigma_sqrt_t,
+                ))
+                .min(PROBABILITY_PRECISION)
+            };
+            match bet.kind {
+                BetKind::Touch => touch,
+                _ => PROBABILITY_PRECISION - touch,
+            }
+        }
+    }
+    .clamp(0, PROBABILITY_PRECISION);
+
+    let fair_value = win_probability as u128 * (bet.wagered_amount + bet.profit_amount) as u128
+        / PROBABILITY_PRECISION as u128;
+
+    Ok(u64::try_from(
+        fair_value * (BASIS_POINTS_DIVISOR - spread_basis_points as u128) / BASIS_POINTS_DIVISOR,
+    )
+    .unwrap())
+}
+
+/// Shift a price by a signed number of basis points
+fn offset_price(price: u64, offset_basis_points: i32) -> u64 {
+    u64::try_from(
+        price as i128 * (BASIS_POINTS_DIVISOR as i128 + offset_basis_points as i128)
+            / BASIS_POINTS_DIVISOR as i128,
+    )
+    .unwrap()
+}
+
+/// Resolve a pending expired bet at the settled price and release its reserved liquidity
+fn settle(bet: &mut Bet, market: &mut Market, house: &mut House, settled_price: u64) -> Result<()> {
+    // validate status
+    require!(bet.status == Status::Pending, OptnError::BetSettled);
+
+    // validate expiry
+    let current_time = Clock::get()?.unix_timestamp;
+    require_gte!(current_time, bet.expires_at, OptnError::BetNotExpired);
+
+    bet.record_price(settled_price);
+
+    if bet.is_won(settled_price) {
+        bet.status = Status::Won;
+        bet.final_payout = bet.wagered_amount + bet.profit_amount;
+
+        house.liquidity -= bet.profit_amount; // house lose
+        house.unclaimed_payouts += bet.final_payout;
+        market.unclaimed_payouts += bet.final_payout;
+    } else {
+        bet.status = Status::Lose;
+
+        // TODO: cut % of house profit for revenue
+        house.liquidity += bet.wagered_amount; // house profit
+    }
+
+    house.active_bets -= 1;
+    house.settled_bets += 1;
+    house.total_wagered -= bet.wagered_amount;
+    house.reserved_liquidity -= bet.profit_amount;
+
+    market.active_bets -= 1;
+    market.settled_bets += 1;
+    market.total_wagered -= bet.wagered_amount;
+    market.reserved_liquidity -= bet.profit_amount;
+
+    bet.settled_price = settled_price;
+    bet.settled_at = Some(current_time);
+
+    Ok(())
+}
+
+/// Take the beneficiary's free liquidity out of the house at the minimum liquidity per share,
+/// leaving enough for everything else it owes
+fn withdraw(house: &mut House, house_authority: &AccountInfo, lamports: u64) -> Result<()> {
+    require_gt!(lamports, 0);
+
+    house.share_unshared_liquidity();
+
+    let available_liquidity = house.min_liquidity();
+    require_gte!(
+        available_liquidity,
+        lamports,
+        OptnError::InsufficientLiquidity
+    );
+
+    // validate the balance left still covers open wagers, house profit and unclaimed payouts
+    let owed = house.total_wagered + house.total_profit + house.unclaimed_payouts;
+    require_gte!(
+        house_authority.lamports().saturating_sub(owed),
+        lamports,
+        OptnError::InsufficientLiquidity
+    );
+
+    validate_rent_floor(house_authority, lamports)?;
+
+    // validate the beneficiary shares cover the withdrawal, rounding the burn up
+    let shares = u64::try_from(
+        (lamports as u128 * house.total_shares as u128).div_ceil(house.min_liquidity() as u128),
+    )
+    .unwrap();
+    require_gte!(house.beneficiary_shares, shares, OptnError::LiquidityShared);
+
+    house.beneficiary_shares -= shares;
+    house.total_shares -= shares;
+    house.liquidity -= lamports;
+    house.total_withdrawals += lamports;
+
+    Ok(())
+}
+
+/// Cancel a pending bet with the given refund and release its reserved liquidity, the caller
+/// closes the bet account
+fn cancel(
+    bet: &mut Bet,
+    market: &mut Market,
+    house: &mut House,
+    refund_amount: u64,
+    current_time: i64,
+) {
+    bet.status = Status::Canceled;
+    bet.final_payout = refund_amount;
+    bet.settled_at = Some(current_time);
+
+    house.active_bets -= 1;
+    house.canceled_bets += 1;
+    house.total_wagered -= bet.wagered_amount;
+    house.reserved_liquidity -= bet.profit_amount;
+
+    market.active_bets -= 1;
+    market.open_bets -= 1;
+    market.canceled_bets += 1;
+    market.total_wagered -= bet.wagered_amount;
+    market.reserved_liquidity -= bet.profit_amount;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: i128, expected: i128, tolerance: i128) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not within {tolerance} of {expected}"
+        );
+    }
+
+    fn bet(kind: BetKind, direction: Direction, range_basis_points: u16) -> Bet {
+        let mut bet = Bet {
+            market: Pubkey::default(),
+            authority: Pubkey::default(),
+            wagered_amount: 1_000_000_000,
+            profit_amount: 1_000_000_000,
+            final_payout: 0,
+            entry_price: 0,
+            strike_price: 0,
+            lower_price: 0,
+            upper_price: 0,
+            high_price: 0,
+            low_price: 0,
+            settled_price: 0,
+            strike_offset_basis_points: 0,
+            range_basis_points,
+            direction,
+            kind,
+            status: Status::Pending,
+            created_at: 0,
+            expires_at: 0,
+            settled_at: None,
+        };
+        bet.set_entry_price(100_000_000);
+        bet
+    }
+
+    #[test]
+    fn ln_ratio_matches_known_values() {
+        assert_eq!(ln_ratio(1, 1), 0);
+        assert_close(ln_ratio(2, 1), 693_147_181, 10);
+        assert_close(ln_ratio(1, 2), -693_147_181, 10);
+        assert_close(ln_ratio(10, 1), 2_302_585_093, 10);
+        assert_close(ln_ratio(2_718_281_828, 1_000_000_000), 1_000_000_000, 10);
+        assert_close(ln_ratio(99, 100), -10_050_336, 10);
+    }
+
+    #[test]
+    fn exp_neg_matches_known_values() {
+        assert_eq!(exp_neg(0), 1_000_000_000);
+        assert_close(exp_neg(LN_2), 500_000_000, 10);
+        assert_close(exp_neg(1_000_000_000), 367_879_441, 10);
+        assert_close(exp_neg(10_000_000_000), 45_400, 10);
+        assert_eq!(exp_neg(100_000_000_000), 0);
+    }
+
+    #[test]
+    fn normal_cdf_matches_known_values() {
+        assert_close(normal_cdf(0), 500_000_000, 100);
+        assert_close(normal_cdf(1_000_000_000), 841_344_746, 100);
+        assert_close(normal_cdf(-1_000_000_000), 158_655_254, 100);
+        assert_close(normal_cdf(1_960_000_000), 975_002_105, 100);
+        assert_close(normal_cdf(-3_000_000_000), 1_349_898, 100);
+        assert_eq!(normal_cdf(20_000_000_000), 1_000_000_000);
+    }
+
+    #[test]
+    fn cash_out_value_matches_known_values() {
+        let year = SECONDS_PER_YEAR as i64;
+
+        // at the money with sigma sqrt(t) = 1 a buy wins with N(-0.5)
+        let buy = bet(BetKind::Binary, Direction::Buy, 0);
+        assert_close(
+            cash_out_value(&buy, 100_000_000, 10_000, 0, year).unwrap() as i128,
+            617_075_078,
+            1_000,
+        );
+        assert_close(
+            cash_out_value(&buy, 100_000_000, 10_000, 100, year).unwrap() as i128,
+            610_904_327,
+            1_000,
+        );
+
+        // the sell side takes the complement, N(0.5)
+        let sell = bet(BetKind::Binary, Direction::Sell, 0);
+        assert_close(
+            cash_out_value(&sell, 100_000_000, 10_000, 0, year).unwrap() as i128,
+            1_382_924_922,
+            1_000,
+        );
+
+        // a touched barrier is certain to pay and a no-touch certain to lose
+        let mut touch = bet(BetKind::Touch, Direction::Buy, 0);
+        touch.strike_price = 110_000_000;
+        touch.record_price(110_000_000);
+        assert_eq!(
+            cash_out_value(&touch, 105_000_000, 10_000, 0, year).unwrap(),
+            2_000_000_000
+        );
+        touch.kind = BetKind::NoTouch;
+        assert_eq!(
+            cash_out_value(&touch, 105_000_000, 10_000, 0, year).unwrap(),
+            0
+        );
+
+        // inside and outside split the full payout between them
+        let inside = bet(BetKind::Inside, Direction::Buy, 1_000);
+        let outside = bet(BetKind::Outside, Direction::Buy, 1_000);
+        let total = cash_out_value(&inside, 100_000_000, 10_000, 0, year).unwrap()
+            + cash_out_value(&outside, 100_000_000, 10_000, 0, year).unwrap();
+        assert_close(total as i128, 2_000_000_000, 1);
+    }
+
+    #[test]
+    fn cash_out_value_rejects_zero_prices() {
+        let buy = bet(BetKind::Binary, Direction::Buy, 0);
+        assert!(cash_out_value(&buy, 0, 10_000, 0, 3_600).is_err());
+
+        let mut zero_strike = buy.clone();
+        zero_strike.strike_price = 0;
+        assert!(cash_out_value(&zero_strike, 100_000_000, 10_000, 0, 3_600).is_err());
+    }
+}